@@ -1,43 +1,307 @@
-use crate::graphics::{Group, TrueSignalClone, VectorEq};
+use crate::graphics::{Group, VectorEq};
 use crate::parser::Modifiers;
 use js_sys::Array;
 use leptos::ev::{self, MouseEvent};
-use leptos::web_sys::{Blob, Url};
+use leptos::web_sys::{Blob, HtmlElement, Url};
 use leptos::Children;
+use leptos::CollectView;
 use leptos::RwSignal;
 use leptos::Show;
 use leptos::Signal;
 use leptos::SignalWith;
 use leptos::{
-    component, create_signal, ev::KeyboardEvent, logging, on_cleanup, provide_context, use_context,
-    view, window, For, IntoView, ReadSignal, SignalUpdate, WriteSignal,
+    component, create_effect, create_signal, ev::KeyboardEvent, logging, on_cleanup,
+    provide_context, use_context, view, window, For, IntoView, Owner, ReadSignal, SignalUpdate,
+    WriteSignal,
+};
+use leptos::{
+    event_target_value, request_animation_frame, window_event_listener, with_owner, SignalSet,
 };
-use leptos::{window_event_listener, SignalSet};
 use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::hash::{DefaultHasher, Hasher};
-use wasm_bindgen::JsValue;
+use std::rc::Rc;
+use wasm_bindgen::{JsCast, JsValue};
 
 use crate::graphics::Circle;
+use crate::settings::{settings, Settings};
 use crate::{
-    graphics::{Form, GraphicsItem, Line, Rect, Text},
-    parser::{Command, CommandType, Coords, CreateComFSM, Direction, FSMResult, RelCoordPair},
+    graphics::{fill_defs, top_level, vimp_metadata, Form, GraphicsItem, Line, Rect, Text},
+    parser::{
+        coords::clamp_to_canvas,
+        is_mark_name,
+        keys::KeyInput,
+        operator::{Operator, OperatorCommand, OperatorFSM, Target},
+        resize::{Resize, ResizeFSM},
+        Command, CommandType, Coords, CreateComFSM, Direction, FSMResult, RelCoordPair,
+        CANVAS_SIZE,
+    },
 };
 
 // TOOD: refactor into separate files
+mod clipboard;
+mod ex;
+mod insert;
+mod keymap;
+mod registers;
+mod selection;
+mod vimprc;
+pub use ex::{CommandLine, ExCommand, ExRegistry, ExResult, FileName};
+pub use insert::Insert;
+pub use keymap::{KeyMode, Keymap};
+pub use registers::Registers;
+pub use vimprc::{KeyFeeder, StartupScript};
 
 #[derive(Clone)]
 struct CursorSetter {
-    x: ReadSignal<u32>,
-    y: ReadSignal<u32>,
-    setx: WriteSignal<u32>,
-    sety: WriteSignal<u32>,
+    x: ReadSignal<f32>,
+    y: ReadSignal<f32>,
+    setx: WriteSignal<f32>,
+    sety: WriteSignal<f32>,
+    marks: RwSignal<HashMap<char, (f32, f32)>>,
+    jumps: RwSignal<JumpList>,
+}
+
+const MAX_JUMPS: usize = 100;
+
+/// cursor positions before moves, ``Ctrl-o``/``Ctrl-i`` walk through them like vim's jump list
+#[derive(Clone, Debug, Default)]
+struct JumpList {
+    jumps: Vec<(f32, f32)>,
+    /// jumps.len() while not walking through the list
+    idx: usize,
+}
+
+impl JumpList {
+    /// like browser history, jumping from the middle of the list forgets the newer jumps
+    fn record(&mut self, from: (f32, f32)) {
+        self.jumps.truncate(self.idx);
+        if self.jumps.last() != Some(&from) {
+            self.jumps.push(from);
+        }
+        if self.jumps.len() > MAX_JUMPS {
+            self.jumps.remove(0);
+        }
+        self.idx = self.jumps.len();
+    }
+    fn back(&mut self, current: (f32, f32)) -> Option<(f32, f32)> {
+        if self.idx == 0 {
+            return None;
+        }
+        if self.idx == self.jumps.len() {
+            // so Ctrl-i can come back here
+            self.record(current);
+            self.idx = self.jumps.len() - 1;
+            if self.idx == 0 {
+                return None;
+            }
+        }
+        self.idx -= 1;
+        Some(self.jumps[self.idx])
+    }
+    fn forward(&mut self) -> Option<(f32, f32)> {
+        if self.idx + 1 >= self.jumps.len() {
+            return None;
+        }
+        self.idx += 1;
+        Some(self.jumps[self.idx])
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct GridSettings {
+    visible: RwSignal<bool>,
+    snap: RwSignal<bool>,
+    spacing: RwSignal<u32>,
+}
+
+impl GridSettings {
+    pub fn new(spacing: u32) -> Self {
+        Self {
+            visible: RwSignal::new(false),
+            snap: RwSignal::new(false),
+            spacing: RwSignal::new(spacing),
+        }
+    }
+    pub fn visible(&self) -> bool {
+        (self.visible)()
+    }
+    pub fn snap(&self) -> bool {
+        (self.snap)()
+    }
+    pub fn spacing(&self) -> u32 {
+        (self.spacing)()
+    }
+    pub fn set_visible(&self, visible: bool) {
+        self.visible.set(visible);
+    }
+    pub fn set_snap(&self, snap: bool) {
+        self.snap.set(snap);
+    }
+    pub fn toggle_visible(&self) {
+        self.visible.update(|v| *v = !*v);
+    }
+    pub fn toggle_snap(&self) {
+        self.snap.update(|s| *s = !*s);
+    }
+    pub fn set_spacing(&self, spacing: u32) {
+        self.spacing.set(spacing.max(1));
+    }
+    /// rounds a point to the nearest grid point that is still on the canvas
+    pub fn snap_point(&self, p: (f32, f32)) -> (f32, f32) {
+        let spacing = self.spacing() as f32;
+        let last = (CANVAS_SIZE / spacing).floor();
+        let snap = |c: f32| (c / spacing).round().clamp(0., last) * spacing;
+        (snap(p.0), snap(p.1))
+    }
+}
+
+const DEFAULT_GRID_SPACING: u32 = 5;
+
+const MAX_ZOOM: f32 = 16.;
+const PAN_STEP: f32 = 10.;
+
+/// view transform of the document, only changes what you see, not the coords of anything
+#[derive(Clone, Copy)]
+pub struct Viewport {
+    zoom: RwSignal<f32>,
+    pan_x: RwSignal<f32>,
+    pan_y: RwSignal<f32>,
+}
+
+impl Viewport {
+    pub fn new() -> Self {
+        Self {
+            zoom: RwSignal::new(1.),
+            pan_x: RwSignal::new(0.),
+            pan_y: RwSignal::new(0.),
+        }
+    }
+    pub fn zoom(&self) -> f32 {
+        (self.zoom)()
+    }
+    /// zooms by factor, while keeping the point around at the same spot on the screen
+    pub fn zoom_by(&self, factor: f32, around: (f32, f32)) {
+        let old = self.zoom();
+        let new = (old * factor).clamp(1., MAX_ZOOM);
+        let pan_x = around.0 - (around.0 - (self.pan_x)()) * old / new;
+        let pan_y = around.1 - (around.1 - (self.pan_y)()) * old / new;
+        self.zoom.set(new);
+        self.set_pan(pan_x, pan_y);
+    }
+    /// pans by a fraction of the visible area, so it feels the same on every zoom level
+    pub fn pan(&self, dx: f32, dy: f32) {
+        let zoom = self.zoom();
+        self.set_pan(
+            (self.pan_x)() + dx * PAN_STEP / zoom,
+            (self.pan_y)() + dy * PAN_STEP / zoom,
+        );
+    }
+    pub fn reset(&self) {
+        self.zoom.set(1.);
+        self.set_pan(0., 0.);
+    }
+    fn set_pan(&self, x: f32, y: f32) {
+        let max = CANVAS_SIZE - CANVAS_SIZE / self.zoom();
+        self.pan_x.set(x.clamp(0., max));
+        self.pan_y.set(y.clamp(0., max));
+    }
+    /// where a point of the document ends up in the window (in percent of the document element)
+    pub fn to_screen(&self, p: (f32, f32)) -> (f32, f32) {
+        let zoom = self.zoom();
+        ((p.0 - (self.pan_x)()) * zoom, (p.1 - (self.pan_y)()) * zoom)
+    }
+    fn transform_style(&self) -> String {
+        format!(
+            "transform-origin: 0 0; transform: scale({}) translate(-{}%, -{}%)",
+            self.zoom(),
+            (self.pan_x)(),
+            (self.pan_y)()
+        )
+    }
+}
+
+/// the real size of the drawing, coords are still percentages of it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DocumentSize {
+    pub name: &'static str,
+    pub width: f32,
+    pub height: f32,
+    pub unit: &'static str,
+}
+
+impl DocumentSize {
+    pub const PRESETS: [DocumentSize; 5] = [
+        DocumentSize::new("1920x1080", 1920., 1080., "px"),
+        DocumentSize::new("1280x720", 1280., 720., "px"),
+        DocumentSize::new("1000x1000", 1000., 1000., "px"),
+        DocumentSize::new("A4 portrait", 210., 297., "mm"),
+        DocumentSize::new("A4 landscape", 297., 210., "mm"),
+    ];
+
+    pub const fn new(name: &'static str, width: f32, height: f32, unit: &'static str) -> Self {
+        Self {
+            name,
+            width,
+            height,
+            unit,
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::PRESETS.into_iter().find(|doc| doc.name == name)
+    }
+    pub fn view_box(&self) -> String {
+        format!("0 0 {} {}", self.width, self.height)
+    }
+    /// css to letterbox an element with this aspect ratio into its (size) container
+    fn letterbox_style(&self) -> String {
+        format!(
+            "aspect-ratio: {} / {}; width: min(100cqw, 100cqh * {})",
+            self.width,
+            self.height,
+            self.width / self.height
+        )
+    }
+}
+
+impl Default for DocumentSize {
+    fn default() -> Self {
+        Self::PRESETS[0]
+    }
 }
 
 #[component]
-pub fn Canvas() -> impl IntoView {
-    let (x, setx) = create_signal(50);
-    let (y, sety) = create_signal(50);
-    provide_context(CursorSetter { x, y, setx, sety });
+pub fn Canvas(
+    /// vimprc that runs after the one stored in local storage
+    #[prop(optional_no_strip)]
+    vimprc: Option<String>,
+) -> impl IntoView {
+    let (x, setx) = create_signal(50.);
+    let (y, sety) = create_signal(50.);
+    provide_context(CursorSetter {
+        x,
+        y,
+        setx,
+        sety,
+        marks: RwSignal::new(HashMap::new()),
+        jumps: RwSignal::new(JumpList::default()),
+    });
+    let grid = GridSettings::new(DEFAULT_GRID_SPACING);
+    provide_context(grid);
+    let settings = Settings::new(grid);
+    settings.load();
+    provide_context(settings);
+    // tracks every option, so this saves whenever one of them changes
+    create_effect(move |_| settings.save());
+    provide_context(RwSignal::new(DocumentSize::default()));
+    provide_context(Viewport::new());
+    provide_context(ExRegistry::new());
+    provide_context(CommandLine::new());
+    provide_context(FileName(RwSignal::new(None)));
+    provide_context(Keymap::new());
+    provide_context(Registers::new());
+    provide_context(StartupScript(vimprc));
     view! {
         <Reader/>
     }
@@ -52,7 +316,7 @@ fn update_pos_relative(rcp: RelCoordPair, cs: &CursorSetter) {
     }
 }
 
-fn calc_coords(coords: &Coords, cs: &CursorSetter) -> (u32, u32, u32, u32) {
+fn calc_coords(coords: &Coords, cs: &CursorSetter) -> (f32, f32, f32, f32) {
     let x = (cs.x)();
     let y = (cs.y)();
 
@@ -62,18 +326,134 @@ fn calc_coords(coords: &Coords, cs: &CursorSetter) -> (u32, u32, u32, u32) {
             let (x2, y2) = fcp.resolve_fcp();
             (x, y, x2, y2)
         }
+        Coords::Mark(_) => {
+            let (x2, y2) = coords.resolve();
+            (x, y, x2, y2)
+        }
     }
 }
 
 /// needs CursorSetter to be in context
-pub fn get_cursor_pos() -> (u32, u32) {
+pub fn get_cursor_pos() -> (f32, f32) {
     let cs = use_context::<CursorSetter>().expect("Will never read this anyways");
     ((cs.x)(), (cs.y)())
 }
 
-fn get_form_vector(p: (u32, u32)) -> (i32, i32) {
+/// needs CursorSetter to be in context
+pub fn get_mark(name: char) -> Option<(f32, f32)> {
+    let cs = use_context::<CursorSetter>().expect("CursorSetter not provided");
+    cs.marks.with(|marks| marks.get(&name).copied())
+}
+
+fn mark_name(key: &str) -> Result<char, String> {
+    let mut chars = key.chars();
+    match (chars.next(), chars.next()) {
+        (Some(name), None) if is_mark_name(name) => Ok(name),
+        _ => Err(format!("Invalid mark: {key}")),
+    }
+}
+
+/// ``m{a-z}``
+fn set_mark(key: &str) -> Result<(), String> {
+    let cs = use_context::<CursorSetter>().unwrap();
+    let name = mark_name(key)?;
+    let pos = get_cursor_pos();
+    cs.marks.update(|marks| {
+        marks.insert(name, pos);
+    });
+    Ok(())
+}
+
+/// ``'{a-z}``
+fn jump_to_mark(key: &str) -> Result<(), String> {
+    let name = mark_name(key)?;
+    let pos = get_mark(name).ok_or_else(|| format!("Mark not set: {name}"))?;
+    jump_to(pos);
+    Ok(())
+}
+
+/// moves the cursor and remembers where it came from in the jump list
+fn jump_to((x, y): (f32, f32)) {
+    let cs = use_context::<CursorSetter>().unwrap();
+    let from = get_cursor_pos();
+    cs.jumps.update(|jumps| jumps.record(from));
+    (cs.setx)(x);
+    (cs.sety)(y);
+}
+
+/// anchors closer than this count as the same point
+const ANCHOR_EPSILON: f32 = 0.01;
+
+/// ``w``/``b``: the next/previous anchor point of any form in reading order (top to bottom,
+/// left to right), wraps around at the end
+fn next_anchor(forward: bool) -> Option<(f32, f32)> {
+    let forms = use_context::<Forms>().unwrap().0;
+    let mut anchors: Vec<_> = forms.with(|forms| {
+        forms
+            .iter()
+            .flat_map(|form| form.anchors())
+            .map(clamp_to_canvas)
+            .collect()
+    });
+    // rounding to a grid of ANCHOR_EPSILON keeps the order transitive, which sort_by needs
+    let quantize = |p: &(f32, f32)| {
+        (
+            (p.1 / ANCHOR_EPSILON).round(),
+            (p.0 / ANCHOR_EPSILON).round(),
+        )
+    };
+    let reading_order = |a: &(f32, f32), b: &(f32, f32)| {
+        let (a, b) = (quantize(a), quantize(b));
+        a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1))
+    };
+    anchors.sort_by(reading_order);
+    let cursor = get_cursor_pos();
+    if forward {
+        anchors
+            .iter()
+            .find(|p| reading_order(p, &cursor) == Ordering::Greater)
+            .or(anchors.first())
+            .copied()
+    } else {
+        anchors
+            .iter()
+            .rev()
+            .find(|p| reading_order(p, &cursor) == Ordering::Less)
+            .or(anchors.last())
+            .copied()
+    }
+}
+
+/// ``Ctrl-o`` (back) and ``Ctrl-i``
+fn walk_jumps(back: bool) {
+    let cs = use_context::<CursorSetter>().unwrap();
+    let current = get_cursor_pos();
+    let target = cs.jumps.try_update(|jumps| {
+        if back {
+            jumps.back(current)
+        } else {
+            jumps.forward()
+        }
+    });
+    if let Some((x, y)) = target.flatten() {
+        (cs.setx)(x);
+        (cs.sety)(y);
+    }
+}
+
+/// needs GridSettings to be in context
+pub fn snap_to_grid(p: (f32, f32)) -> (f32, f32) {
+    let grid = use_context::<GridSettings>().expect("GridSettings not provided");
+    if grid.snap() {
+        grid.snap_point(p)
+    } else {
+        p
+    }
+}
+
+fn get_form_vector(p: (f32, f32)) -> (f32, f32) {
     let p2 = get_cursor_pos();
-    (p.0 as i32 - p2.0 as i32, p.1 as i32 - p2.1 as i32)
+    (p.0 - p2.0, p.1 - p2.1)
 }
 
 fn parse_command(
@@ -81,7 +461,6 @@ fn parse_command(
     set_forms: WriteSignal<Vec<Form>>,
     set_overlays: WriteSignal<Vec<SelectableOverlayData>>,
 ) {
-    let cs = use_context::<CursorSetter>().unwrap();
     let select_mode = use_context::<SelectMode>().unwrap();
     if let SelectState::FormsSelected = select_mode() {
         let buf = use_context::<SelectBuffer>().unwrap().0();
@@ -119,8 +498,8 @@ fn parse_command(
                 com.ctype(),
                 None,
                 Coords::AbsCoord(x, y),
-                // Coords::AbsCoord((x as i32 + vecx) as u32, (y as i32 + vecy) as u32),
                 com.color(),
+                com.corners(),
                 Modifiers::new_with_state(
                     com.mods().move_cursor(),
                     false,
@@ -136,22 +515,21 @@ fn parse_command(
                 None,
                 com.coords(),
                 None,
+                None,
                 Modifiers::new(),
             ));
         }
         if com.mods().cursor_is_middle() {
             let cursor_pos = get_cursor_pos();
             let vec = get_form_vector(com.coords().resolve());
-            let start_coords = Coords::AbsCoord(
-                (cursor_pos.0 as i32 - vec.0) as u32,
-                (cursor_pos.1 as i32 - vec.1) as u32,
-            );
+            let start_coords = Coords::AbsCoord(cursor_pos.0 - vec.0, cursor_pos.1 - vec.1);
             parse_command(
                 Command::new(
                     com.ctype(),
                     Some(start_coords),
                     com.coords(),
                     com.color(),
+                    com.corners(),
                     Modifiers::new_with_state(
                         com.mods().move_cursor(),
                         com.mods().collide(),
@@ -170,16 +548,16 @@ fn parse_command(
             }
             CommandType::Rectangle => Some(Form::Rect(Rect::try_from(com).unwrap())),
             CommandType::Move => {
-                let (x, y) = match com.coords() {
-                    Coords::AbsCoord(x, y) => (x, y),
-                    Coords::RelCoord(rc) => rc.resolve_fcp(),
-                };
-                (cs.setx)(x);
-                (cs.sety)(y);
+                let (x, y) = com.coords().resolve();
+                jump_to((x, y));
                 logging::log!("New cursor pos: {}, {}", x, y);
                 None
             }
-            CommandType::Text => Some(Form::Text(Text::try_from(com).unwrap())),
+            CommandType::Text => {
+                let text = Text::try_from(com).unwrap();
+                use_context::<Insert>().unwrap().start(text.clone(), 0);
+                Some(Form::Text(text))
+            }
             CommandType::Circle(_) => Some(Form::Circle(Circle::try_from(com).unwrap())),
         };
         if let Some(form) = form {
@@ -221,8 +599,18 @@ pub enum SelectState {
     Off,
     SelectModeOn,
     FormsSelected,
+    /// an operator waits for its target, the labels are shown to pick one
+    OperatorPending,
+    /// ``v``, the selection is the box from this corner to the cursor
+    Visual(f32, f32),
 }
 
+#[derive(Clone)]
+struct SelectBufferWS(WriteSignal<Vec<(usize, Form)>>);
+
+#[derive(Clone)]
+struct SelectModeWS(WriteSignal<SelectState>);
+
 #[derive(Clone)]
 pub struct FormsWS(pub WriteSignal<Vec<Form>>);
 
@@ -245,22 +633,174 @@ fn Reader() -> impl IntoView {
     let (select_mode, set_select_mode) = create_signal(SelectState::Off);
     let (overlays, set_overlays) = create_signal(Vec::<SelectableOverlayData>::new());
     let (preview, set_preview) = create_signal(Option::<Form>::None);
+    // 'm' or MARK while waiting for the name of the mark
+    let (mark_op, set_mark_op) = create_signal(Option::<char>::None);
+    let (op_fsm, set_op_fsm) = create_signal(Option::<OperatorFSM>::None);
+    let (resize_fsm, set_resize_fsm) = create_signal(Option::<ResizeFSM>::None);
     provide_context(Forms(forms));
     provide_context(overlays);
     provide_context(PreviewWS(set_preview));
     provide_context(SelectMode(select_mode));
     provide_context(SelectBuffer(select_buffer));
+    provide_context(SelectBufferWS(set_select_buffer));
+    provide_context(SelectModeWS(set_select_mode));
+    provide_context(FormsWS(set_forms));
+    provide_context(OverlaysWS(set_overlays));
+    let insert = Insert::new();
+    provide_context(insert);
+
+    let grid = use_context::<GridSettings>().unwrap();
+    let doc = use_context::<RwSignal<DocumentSize>>().unwrap();
+    let viewport = use_context::<Viewport>().unwrap();
+    let cmdline = use_context::<CommandLine>().unwrap();
+    let registry = use_context::<ExRegistry>().unwrap();
+    let keymap = use_context::<Keymap>().unwrap();
+    let registers = use_context::<Registers>().unwrap();
+    let owner = Owner::current().unwrap();
+
+    // the forms in the box are highlighted while it is being drawn
+    create_effect(move |_| {
+        if let SelectState::Visual(x, y) = select_mode() {
+            let selection = visual_selection((x, y));
+            overlays.with(|vec| {
+                for (i, overlay) in vec.iter().enumerate() {
+                    overlay.set_selected(selection.contains(&i));
+                }
+            });
+        }
+    });
 
     let last_idx: RefCell<Option<usize>> = RefCell::new(None);
     let last_len: RefCell<Option<usize>> = RefCell::new(None);
 
+    // returns whether the key did something, so the browser's own binding can be suppressed
+    let on_ctrl_key = move |key: &str| {
+        match key {
+            "h" => viewport.pan(-1., 0.),
+            "j" => viewport.pan(0., 1.),
+            "k" => viewport.pan(0., -1.),
+            "l" => viewport.pan(1., 0.),
+            "+" | "=" => viewport.zoom_by(2., get_cursor_pos()),
+            "-" => viewport.zoom_by(0.5, get_cursor_pos()),
+            "0" => viewport.reset(),
+            "o" => walk_jumps(true),
+            "i" => walk_jumps(false),
+            _ => return false,
+        }
+        true
+    };
+
     // FIXME: in urgent need of a refactor
     // TODO: in urgent need of a refactor
     // BUG: in urgent need of a refactor
-    let on_keypress = move |evt: KeyboardEvent| {
-        let mut next_char = evt.key();
+    let on_key = Rc::new(move |input: KeyInput| {
+        if input.ctrl {
+            on_ctrl_key(&input.key);
+            return;
+        }
+        let mut next_char = input.key;
         logging::log!("We got {next_char}!");
         logging::log!("Select mode: {:?}", select_mode());
+        if insert.is_active() {
+            insert.handle_key(&next_char);
+            return;
+        }
+        if cmdline.is_open() {
+            cmdline.handle_key(&next_char, &registry, owner);
+            return;
+        }
+        cmdline.clear_message();
+        if let Some(op) = mark_op() {
+            set_mark_op(None);
+            set_com.update(|str| str.clear());
+            let res = match op {
+                _ if next_char == "Escape" => Ok(()),
+                'm' => set_mark(&next_char),
+                _ => jump_to_mark(&next_char),
+            };
+            if let Err(err) = res {
+                cmdline.show(Err(err));
+            }
+            return;
+        }
+        if registers.is_awaiting_name() {
+            if next_char == "Escape" {
+                registers.cancel();
+            } else if let Err(err) = registers.select(&next_char) {
+                cmdline.show(Err(err));
+            }
+            return;
+        }
+        if let Some(op) = op_fsm() {
+            let stop = move || {
+                set_op_fsm(None);
+                set_com.update(|str| str.clear());
+                set_select_mode(SelectState::Off);
+            };
+            match &*next_char {
+                "Escape" => stop(),
+                "Backspace" => {
+                    set_com.update(|str| {
+                        str.pop();
+                    });
+                    match OperatorFSM::from_keys(&com()) {
+                        Some(op) => set_op_fsm(Some(op)),
+                        None => stop(),
+                    }
+                }
+                _ => {
+                    let Some(next_char) = key_char(&next_char) else {
+                        return;
+                    };
+                    set_com.update(|str| str.push(next_char));
+                    match op.advance(next_char) {
+                        Ok(com) => {
+                            stop();
+                            if let Err(err) = run_operator(com) {
+                                cmdline.show(Err(err));
+                            }
+                        }
+                        Err(op) => set_op_fsm(Some(op)),
+                    }
+                }
+            }
+            return;
+        }
+        if let Some(resize) = resize_fsm() {
+            let stop = move || {
+                set_resize_fsm(None);
+                set_com.update(|str| str.clear());
+            };
+            match &*next_char {
+                "Escape" => stop(),
+                "Backspace" => {
+                    set_com.update(|str| {
+                        str.pop();
+                    });
+                    // the keys after the ``s``
+                    match com().get(1..).and_then(ResizeFSM::from_keys) {
+                        Some(resize) => set_resize_fsm(Some(resize)),
+                        None => stop(),
+                    }
+                }
+                _ => {
+                    let Some(next_char) = key_char(&next_char) else {
+                        return;
+                    };
+                    set_com.update(|str| str.push(next_char));
+                    match resize.advance(next_char) {
+                        Ok(Resize { handle, by }) => {
+                            stop();
+                            for (_, form) in select_buffer() {
+                                form.resize(handle, by);
+                            }
+                        }
+                        Err(resize) => set_resize_fsm(Some(resize)),
+                    }
+                }
+            }
+            return;
+        }
         match select_mode() {
             SelectState::SelectModeOn => {
                 if next_char.len() == 1 {
@@ -333,36 +873,54 @@ fn Reader() -> impl IntoView {
                 return;
             }
             SelectState::FormsSelected => match &*next_char {
+                "i" | "A" if fsm().is_none() => {
+                    let text = select_buffer()
+                        .into_iter()
+                        .find_map(|(_, form)| match form {
+                            Form::Text(text) => Some(text),
+                            _ => None,
+                        });
+                    clear_select(
+                        set_com,
+                        set_fsm,
+                        set_select_mode,
+                        set_overlays,
+                        select_buffer,
+                        set_select_buffer,
+                    );
+                    match text {
+                        // A appends, the caret gets clamped to the end
+                        Some(text) => {
+                            insert.start(text, if next_char == "i" { 0 } else { usize::MAX })
+                        }
+                        None => cmdline.show(Err("No text selected".to_string())),
+                    }
+                    return;
+                }
+                "s" if fsm().is_none() => {
+                    set_resize_fsm(Some(ResizeFSM::default()));
+                    set_com(next_char);
+                    return;
+                }
                 "d" | "y" => {
+                    let (idxs, selected): (Vec<_>, Vec<_>) = select_buffer().into_iter().unzip();
+                    registers.store(&selected);
                     if next_char == "d" {
-                        for form in select_buffer() {
-                            set_overlays.update(|vec| {
-                                vec.remove(form.0);
-                            });
-                            set_forms.update(|vec| {
-                                vec.remove(form.0);
-                            });
-                        }
+                        remove_forms(idxs);
                         set_select_buffer.update(|vec| vec.clear());
                     }
-                    logging::log!("made it here");
-                    set_select_mode(SelectState::Off);
-                    set_overlays.update(|vec| {
-                        select_buffer()
-                            .iter()
-                            .map(|el| el.0)
-                            .for_each(|i| vec[i].selected.set(false));
-                    });
-                    set_com.update(|str| str.clear());
+                    clear_select(
+                        set_com,
+                        set_fsm,
+                        set_select_mode,
+                        set_overlays,
+                        select_buffer,
+                        set_select_buffer,
+                    );
                     return;
                 }
                 "g" => {
-                    set_forms.update(|vec| {
-                        let group =
-                            Group::from_iter(select_buffer().iter().map(|tuple| tuple.1.clone()));
-                        set_overlays.update(|vec| vec.push(group.get_overlay_dims()));
-                        vec.push(Form::Group(group));
-                    });
+                    group_forms(select_buffer().into_iter().map(|tuple| tuple.1));
                     clear_select(
                         set_com,
                         set_fsm,
@@ -375,10 +933,39 @@ fn Reader() -> impl IntoView {
                 }
                 _ => {}
             },
+            SelectState::Visual(x, y) if fsm().is_none() => match &*next_char {
+                "v" | "Enter" => {
+                    set_com.update(|str| str.clear());
+                    let selection = visual_selection((x, y));
+                    if selection.is_empty() {
+                        cmdline.show(Err("No forms in the selection".to_string()));
+                        set_select_mode(SelectState::Off);
+                        return;
+                    }
+                    set_select_buffer(
+                        selection
+                            .into_iter()
+                            .map(|i| (i, forms.with(|vec| vec[i].clone())))
+                            .collect(),
+                    );
+                    set_select_mode(SelectState::FormsSelected);
+                    return;
+                }
+                "Escape" => overlays.with(|vec| {
+                    for overlay in vec {
+                        overlay.set_selected(false);
+                    }
+                }),
+                // only what moves the cursor (or doesn't touch the forms) gets through
+                ":" | "Backspace" | "w" | "b" | "'" | "#" | "%" | "<" | ">" | "-" | "a" | "M" => {}
+                key if key.chars().all(|c| c.is_ascii_digit()) => {}
+                _ => return,
+            },
             _ => {}
         }
         match &*next_char {
             "Escape" => {
+                registers.cancel();
                 clear_select(
                     set_com,
                     set_fsm,
@@ -392,22 +979,80 @@ fn Reader() -> impl IntoView {
                 set_select_mode(SelectState::SelectModeOn);
                 return;
             }
-            "p" => {
-                set_select_buffer.update(|select_buffer| {
-                    for form in select_buffer.iter().map(|(_, form)| form) {
-                        if let Form::Group(_) = form {
-                            provide_context(FormsWS(set_forms));
-                            provide_context(OverlaysWS(set_overlays));
-                        }
-                        let form = form.deep_clone();
-                        let (x, y) = get_cursor_pos();
-                        form.move_form(&Coords::AbsCoord(x, y));
-                        set_overlays.update(|vec| vec.push(form.get_overlay_dims()));
-                        set_forms.update(|vec| {
-                            vec.push(form);
-                        });
+            "i" if fsm().is_none() && select_mode() == SelectState::Off => {
+                // like ``t<CR>``
+                parse_command(
+                    Command::new(
+                        CommandType::Text,
+                        None,
+                        Coords::from_cursor(),
+                        None,
+                        None,
+                        Modifiers::new(),
+                    ),
+                    set_forms,
+                    set_overlays,
+                );
+                return;
+            }
+            "v" if fsm().is_none() && select_mode() == SelectState::Off => {
+                let (x, y) = get_cursor_pos();
+                set_select_mode(SelectState::Visual(x, y));
+                return;
+            }
+            "d" | "y" | "g" | "C" if fsm().is_none() && select_mode() == SelectState::Off => {
+                set_op_fsm(OperatorFSM::new(next_char.chars().next().unwrap()));
+                set_com(next_char);
+                set_select_mode(SelectState::OperatorPending);
+                return;
+            }
+            ":" if fsm().is_none() => {
+                cmdline.open();
+                return;
+            }
+            "#" if fsm().is_none() => {
+                grid.toggle_visible();
+                return;
+            }
+            "%" if fsm().is_none() => {
+                grid.toggle_snap();
+                return;
+            }
+            ">" if fsm().is_none() => {
+                grid.set_spacing(grid.spacing() + 1);
+                return;
+            }
+            "<" if fsm().is_none() => {
+                grid.set_spacing(grid.spacing().saturating_sub(1));
+                return;
+            }
+            "w" | "b" if fsm().is_none() => {
+                if let Some((x, y)) = next_anchor(next_char == "w") {
+                    let cs = use_context::<CursorSetter>().unwrap();
+                    (cs.setx)(x);
+                    (cs.sety)(y);
+                }
+                return;
+            }
+            "m" | "'" if fsm().is_none() => {
+                set_mark_op(next_char.chars().next());
+                set_com(next_char);
+                return;
+            }
+            "\"" if fsm().is_none() => {
+                registers.await_name();
+                return;
+            }
+            "p" if fsm().is_none() => {
+                if registers.uses_clipboard() {
+                    registers.cancel();
+                    clipboard::paste(paste_forms);
+                } else {
+                    match registers.paste() {
+                        Ok(pasted) => paste_forms(pasted),
+                        Err(err) => cmdline.show(Err(err)),
                     }
-                });
+                }
                 clear_select(
                     set_com,
                     set_fsm,
@@ -486,17 +1131,77 @@ fn Reader() -> impl IntoView {
             }
             update_preview(&fsm);
         }
+    });
+    let key_mode = move || {
+        if cmdline.is_open() {
+            KeyMode::CommandLine
+        } else if insert.is_active() {
+            KeyMode::Insert
+        } else if op_fsm().is_some() || resize_fsm().is_some() {
+            KeyMode::Pending
+        } else if !matches!(select_mode(), SelectState::Off | SelectState::Visual(..)) {
+            KeyMode::Select
+        } else if fsm().is_some() {
+            KeyMode::Pending
+        } else {
+            KeyMode::Normal
+        }
+    };
+    let describe = Signal::derive(move || {
+        fsm()
+            .map(|fsm| fsm.describe())
+            .or_else(|| op_fsm().map(|op| op.describe()))
+            .or_else(|| resize_fsm().map(|resize| resize.describe()))
+    });
+    let feeder = Rc::new(move |key: KeyInput| {
+        if let Err(err) = keymap.feed(key, key_mode, &*on_key) {
+            logging::error!("{err}");
+            cmdline.show(Err(err));
+        }
+    });
+    provide_context(KeyFeeder(feeder.clone()));
+
+    let on_keypress = move |evt: KeyboardEvent| {
+        let key = KeyInput::new(&evt.key(), evt.ctrl_key());
+        if key.ctrl {
+            // unmapped ctrl keys that vimp doesn't use stay with the browser
+            if !keymap.is_mapped(&key, key_mode()) {
+                if on_ctrl_key(&key.key) {
+                    evt.prevent_default();
+                }
+                return;
+            }
+            evt.prevent_default();
+        }
+        feeder(key);
     };
 
     let handle = window_event_listener(ev::keydown, on_keypress);
     on_cleanup(move || handle.remove());
 
+    // deferred, so the keys of the vimprc get typed into a mounted Reader
+    request_animation_frame(move || {
+        if let Err(err) = with_owner(owner, vimprc::run_startup) {
+            cmdline.show(Err(err));
+        }
+    });
+
     view! {
         <ExportBtn/>
         <div class="box">
-            <p>Current command: {com}</p>
+            <StatusLine com={com} describe={describe}/>
             <div class="container">
-            <svg id="svg_canvas" style="width: 100%; height: 100%; position: absolute">
+            <div class="document" style={move || doc().letterbox_style()}>
+            <Rulers/>
+            <div class="viewport" style={move || viewport.transform_style()}>
+            <Grid/>
+            <svg id="svg_canvas" viewBox={move || doc().view_box()} style="width: 100%; height: 100%; position: absolute">
+                // gradients and hatches, the preview needs its own too
+                <defs inner_html={move || {
+                    let mut forms = forms();
+                    forms.extend(preview());
+                    doc.with(|doc| fill_defs(&forms, doc))
+                }}/>
                 {move ||
                     if let Some(form) = preview() {
                         form.into_view()
@@ -510,9 +1215,12 @@ fn Reader() -> impl IntoView {
                         view! {{el.into_view()}}
                     }
                 />
+                {move || insert.caret_view()}
             </svg>
             <Cursor/>
             </div>
+            </div>
+            </div>
         </div>
     }
 }
@@ -528,41 +1236,214 @@ fn update_preview(fsm: &ReadSignal<Option<CreateComFSM>>) {
 }
 
 #[component]
-fn ExportBtn() -> impl IntoView {
-    let (download_link, set_download_link) = create_signal(Option::<String>::None);
-    let export = move |_| {
-        let doc = match window().document() {
-            Some(doc) => doc,
-            None => {
-                logging::error!("Document property not found (this is a major invalid state)!");
-                panic!("Document no work");
-            }
-        };
-        let svg = match doc.get_element_by_id("svg_canvas") {
-            Some(el) => el,
-            None => panic!("BUG: svg canvas has wrong id!"),
-        }
-        .inner_html();
-        let svg = format!(
-            "<?xml version=\"1.0\" standalone=\"no\"?>
-<svg height=\"100%\" width=\"100%\" version=\"1.1\"
+fn StatusLine(
+    com: ReadSignal<String>,
+    /// what the command typed so far would do
+    describe: Signal<Option<String>>,
+) -> impl IntoView {
+    let cs = use_context::<CursorSetter>().unwrap();
+    let select_mode = use_context::<SelectMode>().unwrap();
+    let select_buffer = use_context::<SelectBuffer>().unwrap().0;
+    let grid = use_context::<GridSettings>().unwrap();
+    let settings = settings();
+    let insert = use_context::<Insert>().unwrap();
+    let mode = move || match select_mode() {
+        _ if insert.is_active() => "INSERT",
+        SelectState::Off => "NORMAL",
+        SelectState::SelectModeOn => "SELECT",
+        SelectState::FormsSelected => "FORMS SELECTED",
+        SelectState::OperatorPending => "OPERATOR PENDING",
+        SelectState::Visual(..) => "VISUAL",
+    };
+    let cmdline = use_context::<CommandLine>().unwrap();
+    let keymap = use_context::<Keymap>().unwrap();
+    let registers = use_context::<Registers>().unwrap();
+    let pending = move || match (cmdline.line(), cmdline.message(), describe()) {
+        (Some(line), _, _) => view! { <span>":"{line}</span> }.into_view(),
+        (None, Some(Ok(msg)), _) => view! { <span>{msg}</span> }.into_view(),
+        (None, Some(Err(err)), _) => view! { <span class="error">{err}</span> }.into_view(),
+        (None, None, Some(desc)) => format!("{} => {}", com(), desc).into_view(),
+        (None, None, None) => com().into_view(),
+    };
+    let selected = move || match select_buffer.with(|buf| buf.len()) {
+        0 => String::new(),
+        len => format!("{len} selected"),
+    };
+    let snap = move || if grid.snap() { "snap" } else { "" };
+    let viewport = use_context::<Viewport>().unwrap();
+    let zoom = move || {
+        if viewport.zoom() > 1. {
+            format!("zoom {}%", viewport.zoom() * 100.)
+        } else {
+            String::new()
+        }
+    };
+    view! {
+        <div class="statusline">
+            <span class="mode">"-- "{mode}" --"</span>
+            <span class="pending">{pending}</span>
+            <span>{move || keymap.pending()}</span>
+            <span>{move || registers.selected().map(|name| format!("\"{name}"))}</span>
+            <span>{selected}</span>
+            <span>{snap}</span>
+            <span>{zoom}</span>
+            <span style={move || format!("color: {}", settings.color())}>{move || settings.color()}</span>
+            <span>{move || (cs.x)()}";"{move || (cs.y)()}</span>
+        </div>
+    }
+}
+
+const RULER_STEP: u32 = 10;
+
+#[component]
+fn Rulers() -> impl IntoView {
+    let cs = use_context::<CursorSetter>().unwrap();
+    let viewport = use_context::<Viewport>().unwrap();
+    let (x, y) = (cs.x, cs.y);
+    // (label, position on screen) of every tick, each ruler only shows the ones visible on its axis
+    let ticks = move || {
+        let step = (RULER_STEP as f32 / viewport.zoom()).max(1.);
+        (0..)
+            .map(|i| i as f32 * step)
+            .take_while(|c| *c < CANVAS_SIZE)
+            .map(|c| (c, viewport.to_screen((c, c))))
+            .collect::<Vec<_>>()
+    };
+    let visible = |pos: &f32| (0. ..CANVAS_SIZE).contains(pos);
+    let labels = move || {
+        ticks()
+            .into_iter()
+            .filter(|(_, (sx, _))| visible(sx))
+            .map(|(c, (sx, _))| {
+                view! {
+                    <div class="ruler-label" style={format!("left: {sx}%")}>{c}</div>
+                }
+            })
+            .collect_view()
+    };
+    let vlabels = move || {
+        ticks()
+            .into_iter()
+            .filter(|(_, (_, sy))| visible(sy))
+            .map(|(c, (_, sy))| {
+                view! {
+                    <div class="ruler-label" style={format!("top: {sy}%")}>{c}</div>
+                }
+            })
+            .collect_view()
+    };
+    let marker = move || viewport.to_screen((x(), y()));
+    view! {
+        <div class="ruler ruler-horizontal">
+            {labels}
+            <div class="ruler-marker" style={move || format!("left: {}%", marker().0)}>"▼"</div>
+        </div>
+        <div class="ruler ruler-vertical">
+            {vlabels}
+            <div class="ruler-marker" style={move || format!("top: {}%", marker().1)}>"▶"</div>
+        </div>
+    }
+}
+
+#[component]
+fn Grid() -> impl IntoView {
+    let grid = use_context::<GridSettings>().unwrap();
+    let doc = use_context::<RwSignal<DocumentSize>>().unwrap();
+    let lines = move || {
+        let spacing = grid.spacing();
+        (1..=100 / spacing)
+            .map(|i| i * spacing)
+            .map(|c| {
+                let c = format!("{c}%");
+                view! {
+                    <line x1={c.clone()} y1="0%" x2={c.clone()} y2="100%" vector-effect="non-scaling-stroke"/>
+                    <line x1="0%" y1={c.clone()} x2="100%" y2={c} vector-effect="non-scaling-stroke"/>
+                }
+            })
+            .collect_view()
+    };
+    let visible = move || grid.visible();
+    view! {
+        <Show when=visible>
+            <svg id="svg_grid" viewBox={move || doc().view_box()} style="width: 100%; height: 100%; position: absolute">
+                <g style="stroke:#d0d0d0;stroke-width:1">
+                    {lines}
+                </g>
+            </svg>
+        </Show>
+    }
+}
+
+/// svg markup of the whole drawing, like it would be saved in a file
+pub fn export_svg(with_grid: bool) -> String {
+    let doc = match window().document() {
+        Some(doc) => doc,
+        None => {
+            logging::error!("Document property not found (this is a major invalid state)!");
+            panic!("Document no work");
+        }
+    };
+    let svg = match doc.get_element_by_id("svg_canvas") {
+        Some(el) => el,
+        None => panic!("BUG: svg canvas has wrong id!"),
+    }
+    .inner_html();
+    let grid = match doc.get_element_by_id("svg_grid") {
+        Some(el) if with_grid => el.inner_html(),
+        _ => String::new(),
+    };
+    let size = use_context::<RwSignal<DocumentSize>>().unwrap()();
+    // lets :edit and pasting restore groups, labels and fills
+    let metadata = use_context::<Forms>()
+        .unwrap()
+        .0
+        .with(|forms| vimp_metadata(&top_level(forms)));
+    format!(
+        "<?xml version=\"1.0\" standalone=\"no\"?>
+<svg width=\"{}{}\" height=\"{}{}\" viewBox=\"{}\" version=\"1.1\"
      xmlns=\"http://www.w3.org/2000/svg\">
             {}
+            {}
+            {}
 </svg>
 ",
-            svg
-        );
+        size.width,
+        size.unit,
+        size.height,
+        size.unit,
+        size.view_box(),
+        metadata,
+        grid,
+        svg
+    )
+}
+
+pub fn create_object_url(data: &str) -> Result<String, JsValue> {
+    let blob_parts = Array::new_with_length(1);
+    blob_parts.set(0, JsValue::from_str(data));
+    let blob = Blob::new_with_str_sequence(&blob_parts)?;
+    Url::create_object_url_with_blob(&blob)
+}
+
+/// makes the browser download url as file, without the user having to click anything
+pub fn download(url: &str, file: &str) -> Result<(), JsValue> {
+    let doc = window().document().expect("Document no work");
+    let link = doc.create_element("a")?;
+    link.set_attribute("href", url)?;
+    link.set_attribute("download", file)?;
+    link.dyn_into::<HtmlElement>()?.click();
+    Ok(())
+}
+
+#[component]
+fn ExportBtn() -> impl IntoView {
+    let (download_link, set_download_link) = create_signal(Option::<String>::None);
+    let (with_grid, set_with_grid) = create_signal(false);
+    let doc_size = use_context::<RwSignal<DocumentSize>>().unwrap();
+    let export = move |_| {
+        let svg = export_svg(with_grid());
         logging::log!("Svg Data: {svg}");
-        let blob_parts = Array::new_with_length(1);
-        blob_parts.set(0, JsValue::from_str(&svg));
-        let blob = match Blob::new_with_str_sequence(&blob_parts) {
-            Ok(blob) => blob,
-            Err(err) => {
-                logging::error!("Failed to create blob: {err:?}");
-                panic!()
-            }
-        };
-        match Url::create_object_url_with_blob(&blob) {
+        match create_object_url(&svg) {
             Ok(url) => set_download_link(Some(url)),
             Err(err) => {
                 logging::error!("Failed to create URL: {err:?}")
@@ -571,7 +1452,23 @@ fn ExportBtn() -> impl IntoView {
     };
     view! {
         <div style="position: absolute; top: 0%; right: 10%" min-width="20%">
+            <select on:change={move |evt| {
+                if let Some(size) = DocumentSize::from_name(&event_target_value(&evt)) {
+                    doc_size.set(size);
+                }
+            }}>
+                {DocumentSize::PRESETS
+                    .into_iter()
+                    .map(|size| view! {
+                        <option value={size.name} selected={move || doc_size().name == size.name}>{size.name}</option>
+                    })
+                    .collect_view()}
+            </select>
             <button on:click={export}>Export</button>
+            <label>
+                <input type="checkbox" prop:checked={with_grid} on:change={move |_| set_with_grid.update(|b| *b = !*b)}/>
+                Include grid
+            </label>
             {move || {
                 if let Some(url) = download_link() {
                     view! {
@@ -591,10 +1488,10 @@ fn mouseclick(evt: MouseEvent) {
 
 #[derive(Clone, Debug)]
 pub struct SelectableOverlayData {
-    top: Signal<u32>,
-    left: Signal<u32>,
-    end_x: Signal<u32>,
-    end_y: Signal<u32>,
+    top: Signal<f32>,
+    left: Signal<f32>,
+    end_x: Signal<f32>,
+    end_y: Signal<f32>,
     selected: RwSignal<bool>,
 }
 
@@ -612,10 +1509,10 @@ impl IntoView for SelectableOverlayData {
 
 impl SelectableOverlayData {
     pub fn new(
-        top: Signal<u32>,
-        left: Signal<u32>,
-        end_x: Signal<u32>,
-        end_y: Signal<u32>,
+        top: Signal<f32>,
+        left: Signal<f32>,
+        end_x: Signal<f32>,
+        end_y: Signal<f32>,
     ) -> Self {
         Self {
             top,
@@ -627,23 +1524,23 @@ impl SelectableOverlayData {
     }
     fn key(&self) -> u64 {
         let mut hasher = DefaultHasher::new();
-        hasher.write_u32(self.top());
-        hasher.write_u32(self.left());
-        hasher.write_u32(self.end_x());
-        hasher.write_u32(self.end_y());
+        hasher.write_u32(self.top().to_bits());
+        hasher.write_u32(self.left().to_bits());
+        hasher.write_u32(self.end_x().to_bits());
+        hasher.write_u32(self.end_y().to_bits());
         hasher.finish()
     }
 
-    pub fn top(&self) -> u32 {
+    pub fn top(&self) -> f32 {
         (self.top)()
     }
-    pub fn left(&self) -> u32 {
+    pub fn left(&self) -> f32 {
         (self.left)()
     }
-    pub fn end_x(&self) -> u32 {
+    pub fn end_x(&self) -> f32 {
         (self.end_x)()
     }
-    pub fn end_y(&self) -> u32 {
+    pub fn end_y(&self) -> f32 {
         (self.end_y)()
     }
     pub fn set_selected(&self, selected: bool) {
@@ -653,18 +1550,18 @@ impl SelectableOverlayData {
 
 #[component]
 fn SelectableOverlay(
-    top: Signal<u32>,
-    left: Signal<u32>,
-    end_x: Signal<u32>,
-    end_y: Signal<u32>,
+    top: Signal<f32>,
+    left: Signal<f32>,
+    end_x: Signal<f32>,
+    end_y: Signal<f32>,
     selected: ReadSignal<bool>,
     name: String,
 ) -> impl IntoView {
     let style = move || {
         format!(
             "position: absolute; top: {}%; left: {}%; min-width: 5%; min-height: 5%; border: 2px inset; border-radius: 10px; font-size: 1em; text-align: center",
-            top() + ((end_y().checked_sub(top()).unwrap_or(0))/2) as u32,
-            left() + ((end_x().checked_sub(left()).unwrap_or(0))/2) as u32,
+            top() + (end_y() - top()).max(0.) / 2.,
+            left() + (end_x() - left()).max(0.) / 2.,
         )
     };
     let class = move || format!("selectable {}", if selected() { "selected" } else { "" });
@@ -738,6 +1635,21 @@ fn Cursor() -> impl IntoView {
     let overlays = use_context::<ReadSignal<Vec<SelectableOverlayData>>>().unwrap();
     let namer = RwSignal::new(Namer::new());
     provide_context(namer);
+    let visual_mode = select_mode.clone();
+    let visual_box = move || {
+        let SelectState::Visual(ax, ay) = visual_mode() else {
+            return None;
+        };
+        let (x, y) = (x(), y());
+        let style = format!(
+            "position: absolute; top: {}%; left: {}%; width: {}%; height: {}%; border: 1px dashed; box-sizing: border-box",
+            ay.min(y),
+            ax.min(x),
+            (ax - x).abs(),
+            (ay - y).abs()
+        );
+        Some(view! { <div class="visual" style={style}></div> })
+    };
 
     view! {
         <div id="overlay" on:mousedown={mouseclick} style="width: 100%; height: 100%; z-index: 1; position: absolute; box-sizing: border-box"> //  padding-right: 5%; padding-bottom: 2%;
@@ -755,6 +1667,7 @@ fn Cursor() -> impl IntoView {
                 view! {}.into_view()
             }}
         </Show>
+        {visual_box}
         <div style={style}>
             UwU
         </div>
@@ -771,6 +1684,145 @@ pub fn Selectable(edit: ReadSignal<bool>, children: Children) -> impl IntoView {
     }
 }
 
+/// adds forms moved by the cursor position, like ``p`` does
+fn paste_forms(forms: Vec<Form>) {
+    let (x, y) = get_cursor_pos();
+    for form in &forms {
+        form.move_form(&Coords::AbsCoord(x, y));
+    }
+    add_forms(forms);
+}
+
+/// adds forms with their overlays, the forms of groups get added too
+fn add_forms(forms: Vec<Form>) {
+    let set_forms = use_context::<FormsWS>().unwrap().0;
+    let set_overlays = use_context::<OverlaysWS>().unwrap().0;
+    for form in forms {
+        for form in form.flatten() {
+            set_overlays.update(|vec| vec.push(form.get_overlay_dims()));
+            set_forms.update(|vec| {
+                vec.push(form);
+            });
+        }
+    }
+}
+
+/// the char a key types into a command, Enter ends commands like ``<CR>`` does
+fn key_char(key: &str) -> Option<char> {
+    let mut chars = key.chars();
+    match (key, chars.next(), chars.next()) {
+        ("Enter", _, _) => Some('\n'),
+        (_, Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+/// removes forms with their overlays, the indices are the ones before removing anything
+fn remove_forms(mut idxs: Vec<usize>) {
+    let set_forms = use_context::<FormsWS>().unwrap().0;
+    let set_overlays = use_context::<OverlaysWS>().unwrap().0;
+    // from the back, so the other indices stay valid
+    idxs.sort_by_key(|i| std::cmp::Reverse(*i));
+    idxs.dedup();
+    for i in idxs {
+        set_overlays.update(|vec| {
+            vec.remove(i);
+        });
+        set_forms.update(|vec| {
+            vec.remove(i);
+        });
+    }
+}
+
+fn group_forms(forms: impl Iterator<Item = Form>) {
+    let set_forms = use_context::<FormsWS>().unwrap().0;
+    let set_overlays = use_context::<OverlaysWS>().unwrap().0;
+    let group = Group::from_iter(forms);
+    set_overlays.update(|vec| vec.push(group.get_overlay_dims()));
+    set_forms.update(|vec| vec.push(Form::Group(group)));
+}
+
+/// indices of the forms that touch the box from the corner to the cursor
+fn visual_selection(corner: (f32, f32)) -> Vec<usize> {
+    let overlays = use_context::<ReadSignal<Vec<SelectableOverlayData>>>().unwrap();
+    let (x, y) = get_cursor_pos();
+    let (left, right) = (corner.0.min(x), corner.0.max(x));
+    let (top, bottom) = (corner.1.min(y), corner.1.max(y));
+    overlays.with(|vec| {
+        vec.iter()
+            .enumerate()
+            .filter(|(_, o)| {
+                o.left() <= right && o.end_x() >= left && o.top() <= bottom && o.end_y() >= top
+            })
+            .map(|(i, _)| i)
+            .collect()
+    })
+}
+
+/// indices of the forms an operator works on
+fn resolve_target(target: &Target) -> Result<Vec<usize>, String> {
+    let len = use_context::<Forms>().unwrap().0.with(|forms| forms.len());
+    let overlays = use_context::<ReadSignal<Vec<SelectableOverlayData>>>().unwrap();
+    match target {
+        Target::Labels(names) => names
+            .iter()
+            .map(|name| match Namer::get_index(name) {
+                i if i < len => Ok(i),
+                _ => Err(format!("No form named {name}")),
+            })
+            .collect(),
+        Target::Region(coords) => {
+            let ((x1, y1), (x2, y2)) = (get_cursor_pos(), coords.resolve());
+            let (left, right) = (x1.min(x2), x1.max(x2));
+            let (top, bottom) = (y1.min(y2), y1.max(y2));
+            Ok(overlays.with(|vec| {
+                vec.iter()
+                    .enumerate()
+                    .filter(|(_, o)| {
+                        o.left() >= left
+                            && o.end_x() <= right
+                            && o.top() >= top
+                            && o.end_y() <= bottom
+                    })
+                    .map(|(i, _)| i)
+                    .collect()
+            }))
+        }
+        Target::All => Ok((0..len).collect()),
+    }
+}
+
+fn run_operator(com: OperatorCommand) -> Result<(), String> {
+    let idxs = resolve_target(&com.target)?;
+    if idxs.is_empty() {
+        return Err(format!("No forms to {}", com.op));
+    }
+    let forms = use_context::<Forms>().unwrap().0;
+    let targets: Vec<_> = forms.with(|forms| idxs.iter().map(|i| forms[*i].clone()).collect());
+    match com.op {
+        Operator::Delete => {
+            use_context::<Registers>().unwrap().store(&targets);
+            remove_forms(idxs);
+        }
+        Operator::Yank => use_context::<Registers>().unwrap().store(&targets),
+        Operator::Group => group_forms(targets.into_iter()),
+        Operator::Change(color) => {
+            let color = if color.is_empty() {
+                settings().color()
+            } else {
+                color
+            };
+            for form in &targets {
+                form.check_color(&color)?;
+            }
+            for form in targets {
+                form.set_color(&color);
+            }
+        }
+    }
+    Ok(())
+}
+
 fn clear_select(
     set_com: WriteSignal<String>,
     set_fsm: WriteSignal<Option<CreateComFSM>>,
@@ -0,0 +1,61 @@
+use leptos::{spawn_local, use_context, window, with_owner, Owner, RwSignal};
+use wasm_bindgen_futures::JsFuture;
+
+use super::{CommandLine, DocumentSize};
+use crate::graphics::{from_svg, from_vimp, to_svg, to_vimp, Form, VIMP_HEADER};
+use crate::settings::settings;
+
+/// like in vim, ``"+`` and ``"*`` both are the system clipboard
+pub fn is_clipboard(name: char) -> bool {
+    matches!(name, '+' | '*')
+}
+
+/// svg markup by default (it carries the vimp format too), see the clipboard option
+pub fn copy(forms: &[Form]) {
+    let text = match &*settings().clipboard() {
+        "vimp" => to_vimp(forms),
+        _ => to_svg(forms, &use_context::<RwSignal<DocumentSize>>().unwrap()()),
+    };
+    let count = forms.len();
+    let cmdline = use_context::<CommandLine>().unwrap();
+    let promise = window().navigator().clipboard().write_text(&text);
+    spawn_local(async move {
+        cmdline.show(match JsFuture::from(promise).await {
+            Ok(_) => Ok(format!("{count} forms copied to the clipboard")),
+            Err(err) => Err(format!("Couldn't write to the clipboard: {err:?}")),
+        });
+    });
+}
+
+/// used for files too
+pub fn parse(text: &str, doc: &DocumentSize) -> Result<Vec<Form>, String> {
+    if text.trim_start().starts_with(VIMP_HEADER) {
+        from_vimp(text)
+    } else if text.contains("<svg") {
+        from_svg(text, doc)
+    } else {
+        Err("Neither svg nor a vimp drawing".to_string())
+    }
+}
+
+/// reading the clipboard takes a while, paste gets the forms once they are there
+pub fn paste(paste: impl FnOnce(Vec<Form>) + 'static) {
+    let owner = Owner::current().expect("no reactive owner");
+    let cmdline = use_context::<CommandLine>().unwrap();
+    let doc = use_context::<RwSignal<DocumentSize>>().unwrap();
+    let promise = window().navigator().clipboard().read_text();
+    spawn_local(async move {
+        let text = match JsFuture::from(promise).await {
+            Ok(text) => text.as_string().unwrap_or_default(),
+            Err(err) => {
+                cmdline.show(Err(format!("Couldn't read the clipboard: {err:?}")));
+                return;
+            }
+        };
+        // new forms need an owner for their signals
+        with_owner(owner, || match parse(&text, &doc()) {
+            Ok(forms) => paste(forms),
+            Err(err) => cmdline.show(Err(err)),
+        });
+    });
+}
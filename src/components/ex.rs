@@ -0,0 +1,432 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use js_sys::encode_uri_component;
+use leptos::web_sys::{HtmlCanvasElement, HtmlImageElement};
+use leptos::{
+    logging, use_context, window, with_owner, Owner, RwSignal, SignalSet, SignalUpdate, SignalWith,
+};
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::CanvasRenderingContext2d;
+
+use super::{
+    add_forms, clipboard, download, export_svg, keymap, registers, selection, vimprc, CursorSetter,
+    DocumentSize, FormsWS, OverlaysWS, PreviewWS,
+};
+use crate::settings::settings;
+
+/// the file the drawing was loaded from, ``:write`` saves there unless it is given another one
+#[derive(Clone, Copy)]
+pub struct FileName(pub RwSignal<Option<String>>);
+
+/// Ok(Some(msg)) gets shown in the status line, Err(msg) as an error
+pub type ExResult = Result<Option<String>, String>;
+
+#[derive(Clone)]
+pub struct ExCommand {
+    pub name: &'static str,
+    /// shortest abbreviation that still selects this command (like vim's ``:w`` for ``:write``)
+    pub short: &'static str,
+    pub usage: &'static str,
+    pub run: fn(&[&str]) -> ExResult,
+}
+
+impl ExCommand {
+    fn matches(&self, name: &str) -> bool {
+        name.starts_with(self.short) && self.name.starts_with(name)
+    }
+}
+
+#[derive(Clone)]
+pub struct ExRegistry {
+    commands: Vec<ExCommand>,
+    /// everything after the name of the running command, for args that keep their spaces
+    raw_args: Rc<RefCell<String>>,
+}
+
+impl ExRegistry {
+    pub fn new() -> Self {
+        let mut ret = Self {
+            commands: Vec::new(),
+            raw_args: Rc::new(RefCell::new(String::new())),
+        };
+        ret.register(ExCommand {
+            name: "write",
+            short: "w",
+            usage: "write [file]",
+            run: write,
+        });
+        ret.register(ExCommand {
+            name: "edit",
+            short: "e",
+            usage: "edit [file] (asks for the file)",
+            run: edit,
+        });
+        ret.register(ExCommand {
+            name: "export",
+            short: "exp",
+            usage: "export svg|png [file]",
+            run: export,
+        });
+        ret.register(ExCommand {
+            name: "clear",
+            short: "cle",
+            usage: "clear",
+            run: clear,
+        });
+        ret.register(ExCommand {
+            name: "layer",
+            short: "la",
+            usage: "layer (vimp has no layers)",
+            run: layer,
+        });
+        ret.register(ExCommand {
+            name: "size",
+            short: "si",
+            usage: "size [preset]",
+            run: size,
+        });
+        ret.register(ExCommand {
+            name: "set",
+            short: "se",
+            usage: "set [option[=value]|option?|nooption|option!]...",
+            run: set,
+        });
+        ret.register(ExCommand {
+            name: "color",
+            short: "col",
+            usage: "color [color]",
+            run: color,
+        });
+        ret.register(ExCommand {
+            name: "source",
+            short: "so",
+            usage: "source (asks for a file)",
+            run: vimprc::source,
+        });
+        ret.register(ExCommand {
+            name: "vimprc",
+            short: "vimprc",
+            usage: "vimprc [clear|show]",
+            run: vimprc::vimprc,
+        });
+        ret.register(ExCommand {
+            name: "marks",
+            short: "marks",
+            usage: "marks",
+            run: marks,
+        });
+        ret.register(ExCommand {
+            name: "registers",
+            short: "reg",
+            usage: "registers",
+            run: registers::registers,
+        });
+        ret.register(ExCommand {
+            name: "select",
+            short: "sel",
+            usage: "select all|invert|members|color=<color>|line|rect|circle|text|group...",
+            run: selection::select,
+        });
+        ret.register(ExCommand {
+            name: "change",
+            short: "cha",
+            usage: "change color|fill|stroke|font|family|bold|italic|anchor|rx|ry|text|label=<value>...",
+            run: selection::change,
+        });
+        for command in keymap::COMMANDS {
+            ret.register(command);
+        }
+        ret.register(ExCommand {
+            name: "help",
+            short: "h",
+            usage: "help [command]",
+            run: help,
+        });
+        ret
+    }
+
+    /// later registrations win, so commands can be overridden
+    pub fn register(&mut self, command: ExCommand) {
+        self.commands.insert(0, command);
+    }
+
+    pub fn find(&self, name: &str) -> Option<&ExCommand> {
+        self.commands.iter().find(|com| com.matches(name))
+    }
+
+    pub fn raw_args(&self) -> String {
+        self.raw_args.borrow().clone()
+    }
+
+    pub fn execute(&self, line: &str) -> ExResult {
+        let line = line.trim_start();
+        let mut words = line.split_whitespace();
+        let name = match words.next() {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+        let args: Vec<_> = words.collect();
+        *self.raw_args.borrow_mut() = line[name.len()..].trim_start().to_string();
+        match self.find(name) {
+            Some(com) => (com.run)(&args),
+            None => Err(format!("Not an editor command: {name}")),
+        }
+    }
+}
+
+fn help(args: &[&str]) -> ExResult {
+    let registry = use_context::<ExRegistry>().unwrap();
+    match args {
+        [] => {
+            let mut names: Vec<_> = registry.commands.iter().map(|com| com.name).collect();
+            names.sort();
+            names.dedup();
+            Ok(Some(names.join(" ")))
+        }
+        [name] => match registry.find(name) {
+            Some(com) => Ok(Some(format!(":{}", com.usage))),
+            None => Err(format!("Not an editor command: {name}")),
+        },
+        _ => Err("Usage: :help [command]".to_string()),
+    }
+}
+
+fn write(args: &[&str]) -> ExResult {
+    match args {
+        [] => match (use_context::<FileName>().unwrap().0)() {
+            Some(file) => export(&["svg", &file]),
+            None => export(&["svg"]),
+        },
+        [file] => export(&["svg", file]),
+        _ => Err("Usage: :write [file]".to_string()),
+    }
+}
+
+/// browsers can't open files by their path, so this always asks for one,
+/// a given name only replaces the picked one as the file of ``:write``
+fn edit(args: &[&str]) -> ExResult {
+    let name = match args {
+        [] => None,
+        [file] => Some(file.to_string()),
+        _ => return Err("Usage: :edit [file] (asks for the file)".to_string()),
+    };
+    vimprc::with_uploaded_file(move |picked, text| {
+        let cmdline = use_context::<CommandLine>().unwrap();
+        let doc = use_context::<RwSignal<DocumentSize>>().unwrap();
+        match clipboard::parse(&text, &doc()) {
+            Ok(forms) => {
+                let file = name.unwrap_or(picked);
+                let _ = clear(&[]);
+                add_forms(forms);
+                use_context::<FileName>().unwrap().0.set(Some(file.clone()));
+                cmdline.show(Ok(format!("\"{file}\" loaded")));
+            }
+            Err(err) => cmdline.show(Err(err)),
+        }
+    })?;
+    Ok(None)
+}
+
+fn export(args: &[&str]) -> ExResult {
+    let (format, file) = match args {
+        [format] => (*format, format!("image.{format}")),
+        [format, file] => (*format, file.to_string()),
+        _ => return Err("Usage: :export svg|png [file]".to_string()),
+    };
+    match format {
+        "svg" => {
+            let url = super::create_object_url(&export_svg(false))
+                .map_err(|err| format!("Failed to create URL: {err:?}"))?;
+            download(&url, &file).map_err(|err| format!("Download failed: {err:?}"))?;
+        }
+        "png" => export_png(file.clone())?,
+        other => return Err(format!("Unknown export format: {other}")),
+    }
+    Ok(Some(format!("\"{file}\" written")))
+}
+
+const PX_PER_MM: f32 = 96. / 25.4;
+
+/// renders the svg into an offscreen canvas, the download starts once the image got loaded
+fn export_png(file: String) -> Result<(), String> {
+    let size = use_context::<RwSignal<DocumentSize>>().unwrap()();
+    let scale = if size.unit == "mm" { PX_PER_MM } else { 1. };
+    let (width, height) = (size.width * scale, size.height * scale);
+    let url = format!(
+        "data:image/svg+xml;charset=utf-8,{}",
+        encode_uri_component(&export_svg(false))
+    );
+    let img = HtmlImageElement::new().map_err(|err| format!("{err:?}"))?;
+    let onload_img = img.clone();
+    let onload = Closure::once_into_js(move || {
+        let render = || -> Result<(), wasm_bindgen::JsValue> {
+            let canvas: HtmlCanvasElement = window()
+                .document()
+                .expect("Document no work")
+                .create_element("canvas")?
+                .dyn_into()?;
+            canvas.set_width(width as u32);
+            canvas.set_height(height as u32);
+            let ctx: CanvasRenderingContext2d = canvas
+                .get_context("2d")?
+                .expect("canvas without 2d context")
+                .dyn_into()?;
+            ctx.draw_image_with_html_image_element_and_dw_and_dh(
+                &onload_img,
+                0.,
+                0.,
+                width as f64,
+                height as f64,
+            )?;
+            download(&canvas.to_data_url()?, &file)
+        };
+        if let Err(err) = render() {
+            logging::error!("Failed to export png: {err:?}");
+        }
+    });
+    img.set_onload(Some(onload.unchecked_ref()));
+    img.set_src(&url);
+    Ok(())
+}
+
+fn clear(args: &[&str]) -> ExResult {
+    if !args.is_empty() {
+        return Err("Usage: :clear".to_string());
+    }
+    use_context::<FormsWS>().unwrap().0.set(Vec::new());
+    use_context::<OverlaysWS>().unwrap().0.set(Vec::new());
+    use_context::<PreviewWS>().unwrap().0.set(None);
+    Ok(None)
+}
+
+/// only tells that there are no layers, instead of an unknown command
+fn layer(_args: &[&str]) -> ExResult {
+    Err("vimp has no layers, groups (g in select mode) are the closest".to_string())
+}
+
+fn size(args: &[&str]) -> ExResult {
+    let doc_size = use_context::<RwSignal<DocumentSize>>().unwrap();
+    if args.is_empty() {
+        return Ok(Some(doc_size().name.to_string()));
+    }
+    let name = args.join(" ");
+    match DocumentSize::from_name(&name) {
+        Some(size) => {
+            doc_size.set(size);
+            Ok(None)
+        }
+        None => {
+            let presets: Vec<_> = DocumentSize::PRESETS.iter().map(|s| s.name).collect();
+            Err(format!(
+                "Unknown size \"{name}\", try one of: {}",
+                presets.join(", ")
+            ))
+        }
+    }
+}
+
+fn set(args: &[&str]) -> ExResult {
+    let settings = settings();
+    if args.is_empty() {
+        return Ok(Some(settings.to_args().join(" ")));
+    }
+    let mut shown = Vec::new();
+    for arg in args {
+        if let Some(msg) = settings.apply(arg)? {
+            shown.push(msg);
+        }
+    }
+    Ok((!shown.is_empty()).then(|| shown.join(" ")))
+}
+
+fn marks(args: &[&str]) -> ExResult {
+    if !args.is_empty() {
+        return Err("Usage: :marks".to_string());
+    }
+    let cs = use_context::<CursorSetter>().unwrap();
+    let mut marks: Vec<_> = cs.marks.with(|marks| marks.clone()).into_iter().collect();
+    if marks.is_empty() {
+        return Ok(Some("No marks set".to_string()));
+    }
+    marks.sort_by_key(|(name, _)| *name);
+    let marks: Vec<_> = marks
+        .iter()
+        .map(|(name, (x, y))| format!("{name} {x};{y}"))
+        .collect();
+    Ok(Some(marks.join(" | ")))
+}
+
+fn color(args: &[&str]) -> ExResult {
+    match args {
+        [] => set(&["color?"]),
+        [color] => set(&[&format!("color={color}")]),
+        _ => Err("Usage: :color [color]".to_string()),
+    }
+}
+
+/// state of the ``:`` command line, the message is whatever the last command had to say
+#[derive(Clone, Copy)]
+pub struct CommandLine {
+    line: RwSignal<Option<String>>,
+    message: RwSignal<Option<Result<String, String>>>,
+}
+
+impl CommandLine {
+    pub fn new() -> Self {
+        Self {
+            line: RwSignal::new(None),
+            message: RwSignal::new(None),
+        }
+    }
+    pub fn is_open(&self) -> bool {
+        self.line.with(|line| line.is_some())
+    }
+    pub fn open(&self) {
+        self.message.set(None);
+        self.line.set(Some(String::new()));
+    }
+    pub fn line(&self) -> Option<String> {
+        (self.line)()
+    }
+    pub fn message(&self) -> Option<Result<String, String>> {
+        (self.message)()
+    }
+    pub fn show(&self, msg: Result<String, String>) {
+        self.message.set(Some(msg));
+    }
+    pub fn clear_message(&self) {
+        if self.message.with(|msg| msg.is_some()) {
+            self.message.set(None);
+        }
+    }
+
+    /// needs to run with the owner of the Reader, so the commands find their contexts
+    pub fn handle_key(&self, key: &str, registry: &ExRegistry, owner: Owner) {
+        match key {
+            "Escape" => self.line.set(None),
+            "Enter" => {
+                let line = self.line().unwrap_or_default();
+                self.line.set(None);
+                let res = with_owner(owner, || registry.execute(&line));
+                if let Err(err) = &res {
+                    logging::error!("{err}");
+                }
+                self.message.set(res.transpose());
+            }
+            "Backspace" => self.line.update(|line| {
+                if let Some(str) = line {
+                    if str.pop().is_none() {
+                        *line = None;
+                    }
+                }
+            }),
+            _ if key.chars().count() == 1 => self.line.update(|line| {
+                if let Some(str) = line {
+                    str.push_str(key);
+                }
+            }),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use leptos::{use_context, RwSignal, SignalGetUntracked, SignalSet, SignalUpdate, SignalWith};
+
+use super::{remove_forms, Forms};
+use crate::graphics::{Form, Text};
+
+#[derive(Clone)]
+struct Editing {
+    text: Text,
+    /// in chars, not bytes
+    caret: usize,
+}
+
+/// insert mode, keys get typed straight into a text form until Escape
+#[derive(Clone, Copy)]
+pub struct Insert {
+    editing: RwSignal<Option<Editing>>,
+}
+
+fn byte_index(str: &str, caret: usize) -> usize {
+    str.char_indices().nth(caret).map_or(str.len(), |(i, _)| i)
+}
+
+/// the caret one line up or down, at the same column (or the end of a shorter line)
+fn line_wise(str: &str, caret: usize, up: bool) -> usize {
+    // starts of the lines, in chars
+    let mut starts = vec![0];
+    starts.extend(
+        str.chars()
+            .enumerate()
+            .filter(|(_, c)| *c == '\n')
+            .map(|(i, _)| i + 1),
+    );
+    let line = starts
+        .iter()
+        .rposition(|start| *start <= caret)
+        .unwrap_or(0);
+    let target = match up {
+        true if line == 0 => return 0,
+        true => line - 1,
+        false if line + 1 == starts.len() => return str.chars().count(),
+        false => line + 1,
+    };
+    // the end of the target line is right before its newline
+    let end = starts
+        .get(target + 1)
+        .map_or(str.chars().count(), |next| next - 1);
+    (starts[target] + caret - starts[line]).min(end)
+}
+
+impl Insert {
+    pub fn new() -> Self {
+        Self {
+            editing: RwSignal::new(None),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.editing.with(|editing| editing.is_some())
+    }
+
+    /// the caret gets clamped to the end of the text
+    pub fn start(&self, text: Text, caret: usize) {
+        let caret = caret.min(text.content().with(|str| str.chars().count()));
+        self.editing.set(Some(Editing { text, caret }));
+    }
+
+    /// texts that end up empty can't be seen or selected anymore, so they get removed
+    fn stop(&self) {
+        let Some(Editing { text, .. }) = self.editing.get_untracked() else {
+            return;
+        };
+        self.editing.set(None);
+        let content = text.content();
+        if !content.with(|str| str.is_empty()) {
+            return;
+        }
+        let forms = use_context::<Forms>().unwrap().0;
+        let idx = forms.with(|forms| {
+            forms
+                .iter()
+                .position(|form| matches!(form, Form::Text(t) if t.content() == content))
+        });
+        if let Some(i) = idx {
+            remove_forms(vec![i]);
+        }
+    }
+
+    pub fn handle_key(&self, key: &str) {
+        let Some(Editing { text, caret }) = self.editing.get_untracked() else {
+            return;
+        };
+        let content = text.content();
+        let len = content.with(|str| str.chars().count());
+        let caret = match key {
+            "Escape" => return self.stop(),
+            "Backspace" if caret > 0 => {
+                content.update(|str| {
+                    str.remove(byte_index(str, caret - 1));
+                });
+                caret - 1
+            }
+            "Delete" if caret < len => {
+                content.update(|str| {
+                    str.remove(byte_index(str, caret));
+                });
+                caret
+            }
+            "Enter" => {
+                content.update(|str| str.insert(byte_index(str, caret), '\n'));
+                caret + 1
+            }
+            "ArrowLeft" => caret.saturating_sub(1),
+            "ArrowRight" => (caret + 1).min(len),
+            "ArrowUp" => content.with(|str| line_wise(str, caret, true)),
+            "ArrowDown" => content.with(|str| line_wise(str, caret, false)),
+            "Home" => 0,
+            "End" => len,
+            key if key.chars().count() == 1 => {
+                content.update(|str| str.insert_str(byte_index(str, caret), key));
+                caret + 1
+            }
+            _ => caret,
+        };
+        self.editing.update(|editing| {
+            if let Some(editing) = editing {
+                editing.caret = caret;
+            }
+        });
+    }
+
+    pub fn caret_view(&self) -> Option<leptos::View> {
+        self.editing.with(|editing| {
+            editing
+                .as_ref()
+                .map(|editing| editing.text.caret_view(editing.caret))
+        })
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::VecDeque;
+
+use leptos::{use_context, RwSignal, SignalSet, SignalUpdate, SignalWith};
+
+use super::{ExCommand, ExResult};
+use crate::parser::keys::{parse_keys, KeyInput};
+
+/// like vim's 'maxmapdepth', stops mappings that keep expanding into themselves
+const MAX_MAP_DEPTH: usize = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyMode {
+    Normal,
+    /// a command is being typed (coords, fast coords, modifiers, ...)
+    Pending,
+    Select,
+    /// keys typed into the ``:`` command line never get mapped
+    CommandLine,
+    /// neither do keys typed into a text
+    Insert,
+}
+
+impl KeyMode {
+    /// the modes a map command applies to, keyed by its prefix (``n`` for ``:nmap``, ...)
+    fn from_prefix(prefix: &str) -> Vec<Self> {
+        match prefix {
+            "n" => vec![Self::Normal],
+            "o" => vec![Self::Pending],
+            "s" => vec![Self::Select],
+            _ => vec![Self::Normal, Self::Pending, Self::Select],
+        }
+    }
+
+    fn letter(&self) -> char {
+        match self {
+            Self::Normal => 'n',
+            Self::Pending => 'o',
+            Self::Select => 's',
+            Self::CommandLine => 'c',
+            Self::Insert => 'i',
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Mapping {
+    mode: KeyMode,
+    lhs: Vec<KeyInput>,
+    rhs: Vec<KeyInput>,
+    noremap: bool,
+}
+
+impl Mapping {
+    fn format(&self) -> String {
+        let keys = |keys: &[KeyInput]| keys.iter().map(|key| key.to_string()).collect::<String>();
+        let star = if self.noremap { "*" } else { " " };
+        format!(
+            "{} {} {star}{}",
+            self.mode.letter(),
+            keys(&self.lhs),
+            keys(&self.rhs)
+        )
+    }
+}
+
+/// translates keys per mode before they reach the Reader
+#[derive(Clone, Copy)]
+pub struct Keymap {
+    mappings: RwSignal<Vec<Mapping>>,
+    /// typed keys that are the start of a longer mapping
+    pending: RwSignal<Vec<KeyInput>>,
+}
+
+impl Keymap {
+    pub fn new() -> Self {
+        Self {
+            mappings: RwSignal::new(Vec::new()),
+            pending: RwSignal::new(Vec::new()),
+        }
+    }
+
+    pub fn pending(&self) -> String {
+        self.pending
+            .with(|keys| keys.iter().map(|key| key.to_string()).collect())
+    }
+
+    /// whether the key would be handled by a mapping (including finishing a pending one)
+    pub fn is_mapped(&self, key: &KeyInput, mode: KeyMode) -> bool {
+        !self.pending.with(|keys| keys.is_empty())
+            || self.mappings.with(|maps| {
+                maps.iter()
+                    .any(|map| map.mode == mode && map.lhs.first() == Some(key))
+            })
+    }
+
+    fn map(&self, modes: &[KeyMode], lhs: Vec<KeyInput>, rhs: Vec<KeyInput>, noremap: bool) {
+        self.mappings.update(|maps| {
+            for mode in modes {
+                maps.retain(|map| map.mode != *mode || map.lhs != lhs);
+                maps.push(Mapping {
+                    mode: *mode,
+                    lhs: lhs.clone(),
+                    rhs: rhs.clone(),
+                    noremap,
+                });
+            }
+        });
+    }
+
+    fn unmap(&self, modes: &[KeyMode], lhs: &[KeyInput]) -> bool {
+        let mut found = false;
+        self.mappings.update(|maps| {
+            maps.retain(|map| {
+                let hit = modes.contains(&map.mode) && map.lhs == lhs;
+                found |= hit;
+                !hit
+            })
+        });
+        found
+    }
+
+    fn list(&self, modes: &[KeyMode], lhs: &[KeyInput]) -> Vec<String> {
+        self.mappings.with(|maps| {
+            maps.iter()
+                .filter(|map| modes.contains(&map.mode) && map.lhs.starts_with(lhs))
+                .map(Mapping::format)
+                .collect()
+        })
+    }
+
+    /// runs key through the mappings of the current mode and hands the result to sink,
+    /// mode gets asked again for every key, since the keys before may have changed it
+    pub fn feed(
+        &self,
+        key: KeyInput,
+        mode: impl Fn() -> KeyMode,
+        sink: &dyn Fn(KeyInput),
+    ) -> Result<(), String> {
+        // (key, may be remapped)
+        let mut typeahead = VecDeque::from([(key, true)]);
+        let mut depth = 0;
+        while let Some((key, remap)) = typeahead.pop_front() {
+            let mode = mode();
+            if !remap || matches!(mode, KeyMode::CommandLine | KeyMode::Insert) {
+                sink(key);
+                continue;
+            }
+            let mut keys = (self.pending)();
+            keys.push(key);
+            let (longer, exact) = self.mappings.with(|maps| {
+                let maps = maps.iter().filter(|map| map.mode == mode);
+                let longer = maps
+                    .clone()
+                    .any(|map| map.lhs.len() > keys.len() && map.lhs.starts_with(&keys));
+                let exact = maps
+                    .filter(|map| keys.starts_with(&map.lhs))
+                    .max_by_key(|map| map.lhs.len())
+                    .cloned();
+                (longer, exact)
+            });
+            if longer {
+                self.pending.set(keys);
+                continue;
+            }
+            self.pending.set(Vec::new());
+            match exact {
+                Some(map) => {
+                    depth += 1;
+                    if depth > MAX_MAP_DEPTH {
+                        return Err("recursive mapping".to_string());
+                    }
+                    for key in keys[map.lhs.len()..].iter().rev() {
+                        typeahead.push_front((key.clone(), true));
+                    }
+                    // like vim, a rhs that starts with its own lhs doesn't remap that part
+                    let own_lhs = if map.rhs.starts_with(&map.lhs) {
+                        map.lhs.len()
+                    } else {
+                        0
+                    };
+                    for (i, key) in map.rhs.iter().enumerate().rev() {
+                        typeahead.push_front((key.clone(), !map.noremap && i >= own_lhs));
+                    }
+                }
+                None => {
+                    for key in keys[1..].iter().rev() {
+                        typeahead.push_front((key.clone(), true));
+                    }
+                    sink(keys.swap_remove(0));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub const COMMANDS: [ExCommand; 9] = [
+    ExCommand {
+        name: "map",
+        short: "map",
+        usage: "map [lhs [rhs]]",
+        run: |args| map(args, "", false),
+    },
+    ExCommand {
+        name: "noremap",
+        short: "no",
+        usage: "noremap [lhs [rhs]]",
+        run: |args| map(args, "", true),
+    },
+    ExCommand {
+        name: "nmap",
+        short: "nm",
+        usage: "nmap [lhs [rhs]]",
+        run: |args| map(args, "n", false),
+    },
+    ExCommand {
+        name: "nnoremap",
+        short: "nn",
+        usage: "nnoremap [lhs [rhs]]",
+        run: |args| map(args, "n", true),
+    },
+    ExCommand {
+        name: "omap",
+        short: "om",
+        usage: "omap [lhs [rhs]]",
+        run: |args| map(args, "o", false),
+    },
+    ExCommand {
+        name: "onoremap",
+        short: "ono",
+        usage: "onoremap [lhs [rhs]]",
+        run: |args| map(args, "o", true),
+    },
+    ExCommand {
+        name: "smap",
+        short: "smap",
+        usage: "smap [lhs [rhs]]",
+        run: |args| map(args, "s", false),
+    },
+    ExCommand {
+        name: "snoremap",
+        short: "snor",
+        usage: "snoremap [lhs [rhs]]",
+        run: |args| map(args, "s", true),
+    },
+    ExCommand {
+        name: "unmap",
+        short: "unm",
+        usage: "unmap lhs",
+        run: unmap,
+    },
+];
+
+fn map(args: &[&str], prefix: &str, noremap: bool) -> ExResult {
+    let keymap = use_context::<Keymap>().unwrap();
+    let modes = KeyMode::from_prefix(prefix);
+    match args {
+        [] => {
+            let maps = keymap.list(&modes, &[]);
+            if maps.is_empty() {
+                Ok(Some("No mapping found".to_string()))
+            } else {
+                Ok(Some(maps.join(" | ")))
+            }
+        }
+        [lhs] => {
+            let maps = keymap.list(&modes, &parse_keys(lhs));
+            if maps.is_empty() {
+                Err("No mapping found".to_string())
+            } else {
+                Ok(Some(maps.join(" | ")))
+            }
+        }
+        [lhs, rhs @ ..] => {
+            keymap.map(&modes, parse_keys(lhs), parse_keys(&rhs.join(" ")), noremap);
+            Ok(None)
+        }
+    }
+}
+
+fn unmap(args: &[&str]) -> ExResult {
+    let keymap = use_context::<Keymap>().unwrap();
+    match args {
+        [lhs] => {
+            let modes = KeyMode::from_prefix("");
+            if keymap.unmap(&modes, &parse_keys(lhs)) {
+                Ok(None)
+            } else {
+                Err("No such mapping".to_string())
+            }
+        }
+        _ => Err("Usage: :unmap lhs".to_string()),
+    }
+}
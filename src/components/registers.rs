@@ -0,0 +1,151 @@
+use std::collections::{HashMap, VecDeque};
+
+use leptos::{use_context, RwSignal, SignalSet, SignalUpdate, SignalWith};
+
+use super::{clipboard, ExResult};
+use crate::graphics::{top_level, Form, TrueSignalClone};
+
+pub const UNNAMED: char = '"';
+/// ``"0`` is the latest yank or delete, ``"9`` the oldest one still remembered
+const HISTORY_LEN: usize = 10;
+
+/// vim-style registers, a register is picked with ``"x`` before ``y``, ``d`` or ``p``
+#[derive(Clone, Copy)]
+pub struct Registers {
+    unnamed: RwSignal<Vec<Form>>,
+    named: RwSignal<HashMap<char, Vec<Form>>>,
+    history: RwSignal<VecDeque<Vec<Form>>>,
+    /// Some(None) after ``"``, until the name of the register got typed
+    selected: RwSignal<Option<Option<char>>>,
+}
+
+fn deep_clone_all(forms: &[Form]) -> Vec<Form> {
+    forms.iter().map(|form| form.deep_clone()).collect()
+}
+
+impl Registers {
+    pub fn new() -> Self {
+        Self {
+            unnamed: RwSignal::new(Vec::new()),
+            named: RwSignal::new(HashMap::new()),
+            history: RwSignal::new(VecDeque::new()),
+            selected: RwSignal::new(None),
+        }
+    }
+
+    pub fn is_awaiting_name(&self) -> bool {
+        self.selected.with(|sel| matches!(sel, Some(None)))
+    }
+    pub fn await_name(&self) {
+        self.selected.set(Some(None));
+    }
+    /// the register the next yank, delete or paste will use, if one got picked
+    pub fn selected(&self) -> Option<char> {
+        self.selected.with(|sel| sel.flatten())
+    }
+    pub fn cancel(&self) {
+        self.selected.set(None);
+    }
+
+    pub fn select(&self, key: &str) -> Result<(), String> {
+        let mut chars = key.chars();
+        match (chars.next(), chars.next()) {
+            (Some(name), None)
+                if name == UNNAMED
+                    || name.is_ascii_alphanumeric()
+                    || clipboard::is_clipboard(name) =>
+            {
+                self.selected.set(Some(Some(name)));
+                Ok(())
+            }
+            _ => {
+                self.cancel();
+                Err(format!("Invalid register name: {key}"))
+            }
+        }
+    }
+
+    /// stores a copy of forms in the picked register (the history if none got picked),
+    /// uppercase names append like in vim, digits replace that entry of the history,
+    /// the unnamed register always gets them too
+    /// and ``+``/``*`` copy them to the system clipboard
+    pub fn store(&self, forms: &[Form]) {
+        // the copies are never changed, pasting copies them again,
+        // members of groups come along with the copies of their groups
+        let forms = deep_clone_all(&top_level(forms));
+        match self.selected() {
+            Some(name) if clipboard::is_clipboard(name) => clipboard::copy(&forms),
+            Some(name) if name.is_ascii_alphabetic() => self.named.update(|named| {
+                let reg = named.entry(name.to_ascii_lowercase()).or_default();
+                if name.is_ascii_uppercase() {
+                    reg.extend(forms.iter().cloned());
+                } else {
+                    *reg = forms.clone();
+                }
+            }),
+            Some(name) if name.is_ascii_digit() => self.history.update(|history| {
+                let i = name.to_digit(10).unwrap() as usize;
+                if history.len() <= i {
+                    history.resize(i + 1, Vec::new());
+                }
+                history[i] = forms.clone();
+            }),
+            _ => self.history.update(|history| {
+                history.push_front(forms.clone());
+                history.truncate(HISTORY_LEN);
+            }),
+        }
+        self.unnamed.set(forms);
+        self.cancel();
+    }
+
+    /// whether the next paste has to wait for the system clipboard
+    pub fn uses_clipboard(&self) -> bool {
+        self.selected().is_some_and(clipboard::is_clipboard)
+    }
+
+    /// a fresh copy of the picked register, so it can be pasted again and again
+    pub fn paste(&self) -> Result<Vec<Form>, String> {
+        let name = self.selected().unwrap_or(UNNAMED);
+        self.cancel();
+        let forms = self.with_register(name, deep_clone_all);
+        match forms {
+            Some(forms) if !forms.is_empty() => Ok(forms),
+            _ => Err(format!("Nothing in register {name}")),
+        }
+    }
+
+    fn with_register<T>(&self, name: char, f: impl FnOnce(&[Form]) -> T) -> Option<T> {
+        if name == UNNAMED {
+            Some(self.unnamed.with(|forms| f(forms)))
+        } else if let Some(i) = name.to_digit(10) {
+            self.history
+                .with(|history| history.get(i as usize).map(|forms| f(forms)))
+        } else {
+            self.named
+                .with(|named| named.get(&name.to_ascii_lowercase()).map(|forms| f(forms)))
+        }
+    }
+
+    fn describe(&self) -> Vec<String> {
+        let names = [UNNAMED].into_iter().chain('0'..='9').chain('a'..='z');
+        names
+            .filter_map(|name| {
+                let len = self.with_register(name, |forms| forms.len())?;
+                (len > 0).then(|| format!("\"{name} {len}"))
+            })
+            .collect()
+    }
+}
+
+pub fn registers(args: &[&str]) -> ExResult {
+    if !args.is_empty() {
+        return Err("Usage: :registers".to_string());
+    }
+    let regs = use_context::<Registers>().unwrap().describe();
+    if regs.is_empty() {
+        Ok(Some("All registers are empty".to_string()))
+    } else {
+        Ok(Some(format!("{} (forms per register)", regs.join(" | "))))
+    }
+}
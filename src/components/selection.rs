@@ -0,0 +1,157 @@
+use std::collections::HashSet;
+
+use leptos::{use_context, ReadSignal, SignalWith};
+
+use super::{
+    ExRegistry, ExResult, Forms, SelectBuffer, SelectBufferWS, SelectModeWS, SelectState,
+    SelectableOverlayData,
+};
+use crate::graphics::{Form, GraphicsItem};
+
+const KINDS: [&str; 5] = ["line", "rect", "circle", "text", "group"];
+
+/// indices of the forms inside the selected groups (and the groups themselves)
+fn members(selected: &[(usize, Form)], forms: &[Form]) -> Result<HashSet<usize>, String> {
+    let keys: HashSet<_> = selected
+        .iter()
+        .filter(|(_, form)| matches!(form, Form::Group(_)))
+        .flat_map(|(_, group)| group.flatten())
+        .map(|form| form.key())
+        .collect();
+    if keys.is_empty() {
+        return Err("No group selected".to_string());
+    }
+    Ok(forms
+        .iter()
+        .enumerate()
+        .filter(|(_, form)| keys.contains(&form.key()))
+        .map(|(i, _)| i)
+        .collect())
+}
+
+/// ``:select`` with filters that all have to match, ``invert`` flips the result
+/// (or the current selection, if it is the only argument)
+pub fn select(args: &[&str]) -> ExResult {
+    if args.is_empty() {
+        return Err(format!(
+            "Usage: :select all|invert|members|color=<color>|{}...",
+            KINDS.join("|")
+        ));
+    }
+    let forms = use_context::<Forms>().unwrap().0;
+    let selected = use_context::<SelectBuffer>().unwrap().0;
+    let len = forms.with(|forms| forms.len());
+
+    let mut chosen: HashSet<usize> = if args == ["invert"] {
+        selected.with(|buf| buf.iter().map(|(i, _)| *i).collect())
+    } else {
+        (0..len).collect()
+    };
+    let mut invert = false;
+    for arg in args {
+        let keep: HashSet<usize> = match *arg {
+            "all" => continue,
+            "invert" => {
+                invert = !invert;
+                continue;
+            }
+            "members" => selected.with(|buf| forms.with(|forms| members(buf, forms)))?,
+            kind if KINDS.contains(&kind) => {
+                forms.with(|forms| (0..len).filter(|i| forms[*i].kind() == kind).collect())
+            }
+            arg => match arg.strip_prefix("color=") {
+                Some(color) => forms.with(|forms| {
+                    (0..len)
+                        .filter(|i| {
+                            forms[*i]
+                                .color()
+                                .is_some_and(|c| c.eq_ignore_ascii_case(color))
+                        })
+                        .collect()
+                }),
+                None => return Err(format!("Unknown filter: {arg}")),
+            },
+        };
+        chosen.retain(|i| keep.contains(i));
+    }
+    if invert {
+        chosen = (0..len).filter(|i| !chosen.contains(i)).collect();
+    }
+
+    let mut chosen: Vec<_> = chosen.into_iter().collect();
+    chosen.sort();
+    let overlays = use_context::<ReadSignal<Vec<SelectableOverlayData>>>().unwrap();
+    overlays.with(|vec| {
+        for (i, overlay) in vec.iter().enumerate() {
+            overlay.set_selected(chosen.contains(&i));
+        }
+    });
+    let count = chosen.len();
+    use_context::<SelectBufferWS>().unwrap().0(
+        forms.with(|forms| chosen.into_iter().map(|i| (i, forms[i].clone())).collect()),
+    );
+    use_context::<SelectModeWS>().unwrap().0(if count == 0 {
+        SelectState::Off
+    } else {
+        SelectState::FormsSelected
+    });
+    match count {
+        0 => Err("No forms match".to_string()),
+        count => Ok(Some(format!("{count} forms selected"))),
+    }
+}
+
+const PROPERTIES: [&str; 12] = [
+    "color", "fill", "stroke", "font", "family", "bold", "italic", "anchor", "rx", "ry", "text",
+    "label",
+];
+
+/// ``:change prop=value...`` for the selected forms, ``text=`` and ``label=`` take the rest of
+/// the line as it was typed
+pub fn change(args: &[&str]) -> ExResult {
+    let selected = use_context::<SelectBuffer>().unwrap().0;
+    if selected.with(|buf| buf.is_empty()) {
+        return Err("No forms selected".to_string());
+    }
+    if args.is_empty() {
+        return Err(format!(
+            "Usage: :change {}=<value>...",
+            PROPERTIES.join("=|")
+        ));
+    }
+    let raw = use_context::<ExRegistry>().unwrap().raw_args();
+    // where the current arg starts in the raw line, the args are in the same order
+    let mut pos = 0;
+    let mut changes = Vec::new();
+    for arg in args {
+        pos += raw[pos..].find(arg).unwrap_or(0);
+        let Some((name, value)) = arg.split_once('=') else {
+            return Err(format!("Expected <property>=<value>: {arg}"));
+        };
+        if !PROPERTIES.contains(&name) {
+            return Err(format!("Unknown property: {name}"));
+        }
+        if name == "text" || name == "label" {
+            changes.push((name, raw[pos + name.len() + 1..].to_string()));
+            break;
+        }
+        changes.push((name, value.to_string()));
+        pos += arg.len();
+    }
+    // nothing gets changed if one of the forms can't take the color
+    if let Some((_, color)) = changes.iter().find(|(name, _)| *name == "color") {
+        for (_, form) in selected() {
+            form.check_color(color)?;
+        }
+    }
+    for (name, value) in changes {
+        let mut changed = false;
+        for (_, form) in selected() {
+            changed |= form.set_property(name, &value)?;
+        }
+        if !changed {
+            return Err(format!("None of the selected forms has a {name}"));
+        }
+    }
+    Ok(None)
+}
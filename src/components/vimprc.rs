@@ -0,0 +1,139 @@
+use std::rc::Rc;
+
+use leptos::web_sys::HtmlInputElement;
+use leptos::{logging, spawn_local, use_context, window, with_owner, Owner};
+use wasm_bindgen::{closure::Closure, JsCast};
+use wasm_bindgen_futures::JsFuture;
+
+use super::{CommandLine, ExRegistry, ExResult};
+use crate::parser::keys::{parse_keys, KeyInput};
+
+const STORAGE_KEY: &str = "vimp-vimprc";
+
+/// feeds keys into the Reader, as if they were typed
+#[derive(Clone)]
+pub struct KeyFeeder(pub Rc<dyn Fn(KeyInput)>);
+
+/// vimprc handed to the Canvas by whoever mounted it (e.g. read from a path by the native cli)
+#[derive(Clone)]
+pub struct StartupScript(pub Option<String>);
+
+/// runs every line of a vimprc, lines starting with ``:`` are ex commands, ``"`` starts a comment
+/// and everything else gets typed in normal mode, returns the first error
+pub fn run_script(script: &str) -> Result<(), String> {
+    let registry = use_context::<ExRegistry>().unwrap();
+    let feeder = use_context::<KeyFeeder>().unwrap();
+    let mut first_err = None;
+    for (i, line) in script.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('"') {
+            continue;
+        }
+        let res = match line.strip_prefix(':') {
+            Some(ex) => registry.execute(ex).map(|_| ()),
+            None => {
+                parse_keys(line).into_iter().for_each(|key| (feeder.0)(key));
+                Ok(())
+            }
+        };
+        if let Err(err) = res {
+            logging::error!("vimprc line {}: {err}", i + 1);
+            first_err.get_or_insert(format!("vimprc line {}: {err}", i + 1));
+        }
+    }
+    match first_err {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+pub fn load_stored() -> Option<String> {
+    match window().local_storage() {
+        Ok(Some(storage)) => storage.get_item(STORAGE_KEY).ok().flatten(),
+        _ => None,
+    }
+}
+
+fn store(script: Option<&str>) -> Result<(), String> {
+    let storage = match window().local_storage() {
+        Ok(Some(storage)) => storage,
+        _ => return Err("No local storage available".to_string()),
+    };
+    match script {
+        Some(script) => storage.set_item(STORAGE_KEY, script),
+        None => storage.remove_item(STORAGE_KEY),
+    }
+    .map_err(|err| format!("Couldn't store vimprc: {err:?}"))
+}
+
+/// runs the stored vimprc first and the one given to the Canvas afterwards
+pub fn run_startup() -> Result<(), String> {
+    let stored = load_stored().map(|script| run_script(&script));
+    let given = use_context::<StartupScript>()
+        .and_then(|script| script.0)
+        .map(|script| run_script(&script));
+    stored.unwrap_or(Ok(())).and(given.unwrap_or(Ok(())))
+}
+
+/// lets the user pick a file and calls f with its name and content, f runs with the current owner
+pub(super) fn with_uploaded_file(f: impl FnOnce(String, String) + 'static) -> Result<(), String> {
+    let owner = Owner::current().expect("no reactive owner");
+    let input: HtmlInputElement = window()
+        .document()
+        .expect("Document no work")
+        .create_element("input")
+        .and_then(|el| Ok(el.dyn_into()?))
+        .map_err(|err| format!("{err:?}"))?;
+    input.set_type("file");
+    let picked = input.clone();
+    let onchange = Closure::once_into_js(move || {
+        let file = match picked.files().and_then(|files| files.get(0)) {
+            Some(file) => file,
+            None => return,
+        };
+        spawn_local(async move {
+            match JsFuture::from(file.text()).await {
+                Ok(text) => with_owner(owner, || {
+                    f(file.name(), text.as_string().unwrap_or_default())
+                }),
+                Err(err) => logging::error!("Couldn't read file: {err:?}"),
+            }
+        });
+    });
+    input.set_onchange(Some(onchange.unchecked_ref()));
+    input.click();
+    Ok(())
+}
+
+fn show_result(res: Result<(), String>) {
+    let cmdline = use_context::<CommandLine>().unwrap();
+    cmdline.show(res.map(|_| "vimprc done".to_string()));
+}
+
+pub fn source(args: &[&str]) -> ExResult {
+    if !args.is_empty() {
+        return Err("Usage: :source (asks for a file)".to_string());
+    }
+    with_uploaded_file(|_, script| show_result(run_script(&script)))?;
+    Ok(None)
+}
+
+pub fn vimprc(args: &[&str]) -> ExResult {
+    match args {
+        [] => {
+            with_uploaded_file(|_, script| {
+                show_result(store(Some(&script)).and_then(|_| run_script(&script)))
+            })?;
+            Ok(None)
+        }
+        ["clear"] => {
+            store(None)?;
+            Ok(Some("vimprc removed".to_string()))
+        }
+        ["show"] => match load_stored() {
+            Some(script) => Ok(Some(script.lines().collect::<Vec<_>>().join(" | "))),
+            None => Ok(Some("no vimprc stored".to_string())),
+        },
+        _ => Err("Usage: :vimprc [clear|show]".to_string()),
+    }
+}
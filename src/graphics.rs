@@ -1,17 +1,30 @@
-use crate::components::{FormsWS, OverlaysWS, SelectMode, SelectState, SelectableOverlayData};
+use crate::components::{DocumentSize, SelectMode, SelectState, SelectableOverlayData};
 use std::cell::RefCell;
 use std::cmp::min;
 use std::fmt::{Display, Formatter};
 use std::hash::{DefaultHasher, Hash, Hasher};
 use std::rc::Rc;
+use std::str::FromStr;
 
+use leptos::web_sys::HtmlCanvasElement;
 use leptos::{
-    logging, use_context, view, window, IntoView, RwSignal, Signal, SignalSet, SignalUpdate,
+    document, logging, use_context, view, CollectView, IntoView, RwSignal, Signal, SignalSet,
+    SignalUpdate, SignalWith,
 };
+use wasm_bindgen::JsCast;
+use web_sys::CanvasRenderingContext2d;
 
-use crate::parser::{Command, CommandType, Coords};
+use crate::parser::{
+    coords::clamp_to_canvas, resize::Handle, Command, CommandType, Coords, CANVAS_SIZE,
+};
+use crate::settings::{settings, DEFAULT_STROKE_WIDTH};
+
+mod fill;
+mod serialize;
+pub use fill::{fill_defs, plain_color, Fill};
+pub use serialize::{from_svg, from_vimp, to_svg, to_vimp, vimp_metadata, VIMP_HEADER};
 
-const LOREM_IPSUM: &str = "I'm such a silly boykisser";
+pub const DEFAULT_COLOR: &str = "red";
 
 macro_rules! gen_form {
     ($($type:ident),+) => {
@@ -49,6 +62,21 @@ macro_rules! gen_form {
                     $(Self::$type(form) => form.find_collide(veceq)),+
                 }
             }
+            fn anchors(&self) -> Vec<(f32, f32)> {
+                match self {
+                    $(Self::$type(form) => form.anchors()),+
+                }
+            }
+            fn set_color(&self, color: &str) {
+                match self {
+                    $(Self::$type(form) => form.set_color(color)),+
+                }
+            }
+            fn resize(&self, handle: Handle, by: (f32, f32)) {
+                match self {
+                    $(Self::$type(form) => form.resize(handle, by)),+
+                }
+            }
         }
 
         impl IntoView for Form {
@@ -61,17 +89,83 @@ macro_rules! gen_form {
     };
 }
 
-pub fn key_from_four(n1: u32, n2: u32, n3: u32, n4: u32) -> u128 {
-    ((n1 as u128) << 96u128) + ((n2 as u128) << 64u128) + ((n3 as u128) << 32u128) + n4 as u128
+pub fn key_from_four(n1: f32, n2: f32, n3: f32, n4: f32) -> u128 {
+    ((n1.to_bits() as u128) << 96u128)
+        + ((n2.to_bits() as u128) << 64u128)
+        + ((n3.to_bits() as u128) << 32u128)
+        + n4.to_bits() as u128
+}
+/// shrinks a movement, so that none of the points end up outside of the canvas
+fn clamp_movement(points: &[(f32, f32)], movement: (f32, f32)) -> (f32, f32) {
+    let min_x = points.iter().map(|p| p.0).reduce(f32::min).unwrap_or(0.);
+    let max_x = points.iter().map(|p| p.0).reduce(f32::max).unwrap_or(0.);
+    let min_y = points.iter().map(|p| p.1).reduce(f32::min).unwrap_or(0.);
+    let max_y = points.iter().map(|p| p.1).reduce(f32::max).unwrap_or(0.);
+    (
+        movement.0.max(-min_x).min(CANVAS_SIZE - max_x),
+        movement.1.max(-min_y).min(CANVAS_SIZE - max_y),
+    )
 }
 fn format_css<T: Display>(c: T) -> String {
     format!("{}%", c)
 }
+
+/// the label of a shape at the position and angle (in degrees) its label_pos gives;
+/// labels along lines sit on top of them instead of being centered
+fn label_view(
+    label: RwSignal<String>,
+    along: bool,
+    pos: impl Fn(&DocumentSize) -> (f32, f32, f32) + 'static,
+) -> leptos::View {
+    let doc = use_context::<RwSignal<DocumentSize>>().unwrap();
+    (move || {
+        let text = label();
+        if text.is_empty() {
+            return None;
+        }
+        let (x, y, angle) = doc.with(|doc| pos(doc));
+        // rotate() doesn't take percentages
+        let (ux, uy) = doc.with(|doc| (x * doc.width / 100., y * doc.height / 100.));
+        let (baseline, dy) = if along {
+            ("auto", "-0.3em")
+        } else {
+            ("middle", "0")
+        };
+        // the white outline keeps it readable on any fill
+        Some(view! {
+            <text x={format_css(x)} y={format_css(y)} dy={dy} transform={format!("rotate({angle} {ux} {uy})")}
+                text-anchor="middle" dominant-baseline={baseline} fill="black" stroke="white" stroke-width="3" paint-order="stroke">{text}</text>
+        })
+    })
+    .into_view()
+}
 pub trait GraphicsItem: Clone + TrueSignalClone {
     fn key(&self) -> u128;
     fn get_overlay_dims(&self) -> SelectableOverlayData;
     fn move_form(&self, coords: &Coords);
     fn find_collide(&self, veceq: &VectorEq) -> Option<f32>;
+    /// points the cursor can snap to (corners, edge midpoints, centers, endpoints)
+    fn anchors(&self) -> Vec<(f32, f32)>;
+    /// the fill of shapes, the stroke of lines
+    fn set_color(&self, color: &str);
+    /// moves the edge, corner or endpoint the handle points to
+    fn resize(&self, handle: Handle, by: (f32, f32));
+}
+
+/// corners, edge midpoints and center of a box
+fn box_anchors(x: f32, y: f32, end_x: f32, end_y: f32) -> Vec<(f32, f32)> {
+    let (mid_x, mid_y) = ((x + end_x) / 2., (y + end_y) / 2.);
+    vec![
+        (x, y),
+        (mid_x, y),
+        (end_x, y),
+        (x, mid_y),
+        (mid_x, mid_y),
+        (end_x, mid_y),
+        (x, end_y),
+        (mid_x, end_y),
+        (end_x, end_y),
+    ]
 }
 
 pub trait TrueSignalClone {
@@ -82,11 +176,14 @@ gen_form!(Line, Rect, Text, Circle, Group);
 
 #[derive(Clone, Debug)]
 pub struct Line {
-    x1: RwSignal<u32>,
-    y1: RwSignal<u32>,
-    x2: RwSignal<u32>,
-    y2: RwSignal<u32>,
+    x1: RwSignal<f32>,
+    y1: RwSignal<f32>,
+    x2: RwSignal<f32>,
+    y2: RwSignal<f32>,
     color: RwSignal<String>,
+    stroke_width: RwSignal<f32>,
+    /// drawn on the shape, empty for none
+    label: RwSignal<String>,
 }
 
 impl Display for Line {
@@ -110,18 +207,35 @@ impl TrueSignalClone for Line {
             x2: RwSignal::new((self.x2)()),
             y2: RwSignal::new((self.y2)()),
             color: RwSignal::new((self.color)()),
+            stroke_width: RwSignal::new((self.stroke_width)()),
+            label: RwSignal::new((self.label)()),
         }
     }
 }
 
 impl Line {
-    pub fn from(pair: (u32, u32, u32, u32)) -> Self {
+    /// the midpoint, turned along the line but never upside down
+    fn label_pos(&self, doc: &DocumentSize) -> (f32, f32, f32) {
+        let (x1, y1, x2, y2) = ((self.x1)(), (self.y1)(), (self.x2)(), (self.y2)());
+        let (dx, dy) = ((x2 - x1) * doc.width / 100., (y2 - y1) * doc.height / 100.);
+        let mut angle = dy.atan2(dx).to_degrees();
+        if angle > 90. {
+            angle -= 180.;
+        } else if angle < -90. {
+            angle += 180.;
+        }
+        ((x1 + x2) / 2., (y1 + y2) / 2., angle)
+    }
+
+    pub fn from(pair: (f32, f32, f32, f32)) -> Self {
         Line {
             x1: RwSignal::new(pair.0),
             y1: RwSignal::new(pair.1),
             x2: RwSignal::new(pair.2),
             y2: RwSignal::new(pair.3),
-            color: RwSignal::new("red".to_string()),
+            color: RwSignal::new(DEFAULT_COLOR.to_string()),
+            stroke_width: RwSignal::new(DEFAULT_STROKE_WIDTH),
+            label: RwSignal::new(String::new()),
         }
     }
 
@@ -184,22 +298,32 @@ impl GraphicsItem for Line {
         SelectableOverlayData::new(y1.into(), x1.into(), x2.into(), y2.into())
     }
     fn move_form(&self, coords: &Coords) {
-        match coords {
-            Coords::AbsCoord(x, y) => {
-                self.x1.update(|c| *c += x);
-                self.y1.update(|c| *c += y);
-                self.x2.update(|c| *c += x);
-                self.y2.update(|c| *c += y);
-            }
-            Coords::RelCoord(fcp) => {
-                let p1 = fcp.resolve_with_offset(((self.x1)(), (self.y1)()));
-                let p2 = fcp.resolve_with_offset(((self.x2)(), (self.y2)()));
-                self.x1.set(p1.0);
-                self.y1.set(p1.1);
-                self.x2.set(p2.0);
-                self.y2.set(p2.1);
-            }
-        }
+        let (dx, dy) = clamp_movement(
+            &[((self.x1)(), (self.y1)()), ((self.x2)(), (self.y2)())],
+            coords.movement(),
+        );
+        self.x1.update(|c| *c += dx);
+        self.y1.update(|c| *c += dy);
+        self.x2.update(|c| *c += dx);
+        self.y2.update(|c| *c += dy);
+    }
+    fn anchors(&self) -> Vec<(f32, f32)> {
+        let ((x1, y1), (x2, y2)) = (((self.x1)(), (self.y1)()), ((self.x2)(), (self.y2)()));
+        vec![(x1, y1), ((x1 + x2) / 2., (y1 + y2) / 2.), (x2, y2)]
+    }
+    fn set_color(&self, color: &str) {
+        self.color.set(color.to_string());
+    }
+    fn resize(&self, (hx, hy): Handle, (dx, dy): (f32, f32)) {
+        // the endpoint that lies further in the direction of the handle
+        let (x, y) = if hx * (self.x1)() + hy * (self.y1)() > hx * (self.x2)() + hy * (self.y2)() {
+            (self.x1, self.y1)
+        } else {
+            (self.x2, self.y2)
+        };
+        let (nx, ny) = clamp_to_canvas((x() + dx, y() + dy));
+        x.set(nx);
+        y.set(ny);
     }
     fn find_collide(&self, veceq: &VectorEq) -> Option<f32> {
         veceq.intersect(&VectorEq::from(
@@ -217,11 +341,9 @@ pub struct VectorEq {
 }
 
 impl VectorEq {
-    pub fn from(p1: (u32, u32), p2: (u32, u32)) -> Self {
-        let x1 = p1.0 as f32;
-        let x2 = p2.0 as f32;
-        let y1 = p1.1 as f32;
-        let y2 = p2.1 as f32;
+    pub fn from(p1: (f32, f32), p2: (f32, f32)) -> Self {
+        let (x1, y1) = p1;
+        let (x2, y2) = p2;
         let vec = (x2 - x1, y2 - y1);
         let unit_factor = 1. / ((vec.0 * vec.0 + vec.1 * vec.1).sqrt());
         Self {
@@ -245,10 +367,8 @@ impl VectorEq {
         let same_point = self.resolve(ret);
         logging::log!("{same_point:?}");
         // check if intersect is out of bounce
-        if (ve2.vec.0 < 0. && point.0 < ve2.end.0 as u32
-            || ve2.vec.0 > 0. && point.0 > ve2.end.0 as u32)
-            || (ve2.vec.1 < 0. && point.1 < ve2.end.1 as u32
-                || ve2.vec.1 > 0. && point.1 > ve2.end.1 as u32)
+        if (ve2.vec.0 < 0. && point.0 < ve2.end.0 || ve2.vec.0 > 0. && point.0 > ve2.end.0)
+            || (ve2.vec.1 < 0. && point.1 < ve2.end.1 || ve2.vec.1 > 0. && point.1 > ve2.end.1)
         {
             return None;
         }
@@ -261,17 +381,14 @@ impl VectorEq {
         // logging::log!("constant: {k}");
         // Some((k / (ve2.vec.1 - self.vec.1) - (self.start.0 - ve2.start.0)) / self.vec.0)
     }
-    pub fn resolve(&self, k: f32) -> (u32, u32) {
+    pub fn resolve(&self, k: f32) -> (f32, f32) {
         logging::log!("k: {k}");
         logging::log!(
             "Point: ({}, {})",
             (self.start.0 + self.vec.0 * k),
             (self.start.1 + self.vec.1 * k)
         );
-        (
-            (self.start.0 + self.vec.0 * k) as u32,
-            (self.start.1 + self.vec.1 * k) as u32,
-        )
+        (self.start.0 + self.vec.0 * k, self.start.1 + self.vec.1 * k)
     }
 
     fn len(&self) -> f32 {
@@ -301,14 +418,16 @@ pub fn get_intersect_of_two_lines(vec1: (f32, f32), vec2: VectorFn) -> Option<f3
 
 #[derive(Clone, Debug)]
 pub struct Rect {
-    x: RwSignal<u32>,
-    y: RwSignal<u32>,
-    width: RwSignal<u32>,
-    height: RwSignal<u32>,
-    rx: RwSignal<u32>,
-    ry: RwSignal<u32>,
+    x: RwSignal<f32>,
+    y: RwSignal<f32>,
+    width: RwSignal<f32>,
+    height: RwSignal<f32>,
+    rx: RwSignal<f32>,
+    ry: RwSignal<f32>,
     border_color: RwSignal<String>,
     inner_color: RwSignal<String>,
+    /// drawn on the shape, empty for none
+    label: RwSignal<String>,
 }
 
 impl TrueSignalClone for Rect {
@@ -322,11 +441,20 @@ impl TrueSignalClone for Rect {
             ry: RwSignal::new((self.ry)()),
             border_color: RwSignal::new((self.border_color)()),
             inner_color: RwSignal::new((self.inner_color)()),
+            label: RwSignal::new((self.label)()),
         }
     }
 }
 
 impl Rect {
+    fn label_pos(&self) -> (f32, f32, f32) {
+        (
+            (self.x)() + (self.width)() / 2.,
+            (self.y)() + (self.height)() / 2.,
+            0.,
+        )
+    }
+
     fn css_coords_reactive(
         &self,
     ) -> (
@@ -335,10 +463,10 @@ impl Rect {
         impl Fn() -> String,
         impl Fn() -> String,
     ) {
-        let x1: Signal<u32> = Signal::from(self.x);
-        let y1: Signal<u32> = self.y.into();
-        let width: Signal<u32> = self.width.into();
-        let height: Signal<u32> = self.height.into();
+        let x1: Signal<f32> = Signal::from(self.x);
+        let y1: Signal<f32> = self.y.into();
+        let width: Signal<f32> = self.width.into();
+        let height: Signal<f32> = self.height.into();
         // if width() < 0 {
         //     x1 = Signal::derive(move || (x1() as i32 + width()) as u32);
         //     width = Signal::derive(move || -width());
@@ -358,12 +486,7 @@ impl Rect {
 
 impl GraphicsItem for Rect {
     fn key(&self) -> u128 {
-        key_from_four(
-            (self.x)(),
-            (self.y)(),
-            (self.width)() as u32,
-            (self.height)() as u32,
-        )
+        key_from_four((self.x)(), (self.y)(), (self.width)(), (self.height)())
     }
     fn get_overlay_dims(&self) -> SelectableOverlayData {
         let x = self.x.read_only();
@@ -378,16 +501,38 @@ impl GraphicsItem for Rect {
         )
     }
     fn move_form(&self, coords: &Coords) {
-        match coords {
-            Coords::AbsCoord(x, y) => {
-                self.x.update(|c| *c += x);
-                self.y.update(|c| *c += y);
-            }
-            Coords::RelCoord(fcp) => {
-                let p1 = fcp.resolve_with_offset(((self.x)(), (self.y)()));
-                self.x.set(p1.0);
-                self.y.set(p1.1);
-            }
+        let (x, y) = ((self.x)(), (self.y)());
+        let (dx, dy) = clamp_movement(
+            &[(x, y), (x + (self.width)(), y + (self.height)())],
+            coords.movement(),
+        );
+        self.x.update(|c| *c += dx);
+        self.y.update(|c| *c += dy);
+    }
+    fn anchors(&self) -> Vec<(f32, f32)> {
+        let (x, y) = ((self.x)(), (self.y)());
+        box_anchors(x, y, x + (self.width)(), y + (self.height)())
+    }
+    fn set_color(&self, color: &str) {
+        self.inner_color.set(color.to_string());
+    }
+    fn resize(&self, (hx, hy): Handle, (dx, dy): (f32, f32)) {
+        let (x, y) = ((self.x)(), (self.y)());
+        let (end_x, end_y) = (x + (self.width)(), y + (self.height)());
+        // edges can't be dragged past the opposite one
+        if hx < 0. {
+            let left = (x + dx).clamp(0., end_x);
+            self.x.set(left);
+            self.width.set(end_x - left);
+        } else if hx > 0. {
+            self.width.set((end_x + dx).clamp(x, CANVAS_SIZE) - x);
+        }
+        if hy < 0. {
+            let top = (y + dy).clamp(0., end_y);
+            self.y.set(top);
+            self.height.set(end_y - top);
+        } else if hy > 0. {
+            self.height.set((end_y + dy).clamp(y, CANVAS_SIZE) - y);
         }
     }
     fn find_collide(&self, veceq: &VectorEq) -> Option<f32> {
@@ -408,13 +553,99 @@ impl GraphicsItem for Rect {
     }
 }
 
+/// how a text lines up with its x, named like the alignment buttons of text editors
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextAnchor {
+    Left,
+    Center,
+    Right,
+}
+
+impl TextAnchor {
+    /// svg's text-anchor
+    pub fn svg(&self) -> &'static str {
+        match self {
+            Self::Left => "start",
+            Self::Center => "middle",
+            Self::Right => "end",
+        }
+    }
+    /// how much of the width lies left of x
+    fn offset(&self) -> f32 {
+        match self {
+            Self::Left => 0.,
+            Self::Center => 0.5,
+            Self::Right => 1.,
+        }
+    }
+}
+
+impl FromStr for TextAnchor {
+    type Err = String;
+    /// the svg names work too
+    fn from_str(str: &str) -> Result<Self, Self::Err> {
+        Ok(match str {
+            "left" | "start" => Self::Left,
+            "center" | "middle" => Self::Center,
+            "right" | "end" => Self::Right,
+            _ => return Err(format!("Not a text anchor (left, center or right): {str}")),
+        })
+    }
+}
+
+impl Display for TextAnchor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Left => "left",
+                Self::Center => "center",
+                Self::Right => "right",
+            }
+        )
+    }
+}
+
+/// svg text inherits the browser's 16px, so 1em is 16 units of the document
+const EM: f32 = 16.;
+/// distance between the baselines of two lines, in em
+const LINE_HEIGHT: f32 = 1.2;
+/// roughly how far glyphs reach above the baseline, in em
+const ASCENT: f32 = 0.8;
+
+thread_local! {
+    static MEASURE: Option<CanvasRenderingContext2d> = document()
+        .create_element("canvas")
+        .ok()
+        .and_then(|canvas| canvas.unchecked_into::<HtmlCanvasElement>().get_context("2d").ok())
+        .flatten()
+        .map(|ctx| ctx.unchecked_into());
+}
+
+/// width of a line of text in px, like the browser would draw it
+fn text_width(font: &str, line: &str) -> f32 {
+    MEASURE.with(|ctx| {
+        ctx.as_ref().map_or(0., |ctx| {
+            ctx.set_font(font);
+            ctx.measure_text(line)
+                .map_or(0., |metrics| metrics.width() as f32)
+        })
+    })
+}
+
 #[derive(Clone, Debug)]
 pub struct Text {
-    x: RwSignal<u32>,
-    y: RwSignal<u32>,
+    x: RwSignal<f32>,
+    y: RwSignal<f32>,
+    /// lines are separated by ``\n``
     text: RwSignal<String>,
     font_size: RwSignal<u32>,
     color: RwSignal<String>,
+    font_family: RwSignal<String>,
+    bold: RwSignal<bool>,
+    italic: RwSignal<bool>,
+    anchor: RwSignal<TextAnchor>,
 }
 
 impl Text {
@@ -423,6 +654,72 @@ impl Text {
         let y = self.y;
         (move || format_css((x)()), move || format_css((y)()))
     }
+
+    /// what gets typed in insert mode
+    pub fn content(&self) -> RwSignal<String> {
+        self.text
+    }
+
+    /// the css of everything but the color
+    fn style(self) -> impl Fn() -> String {
+        move || {
+            format!(
+                "font-size: {}em; font-family: {}; font-weight: {}; font-style: {}; white-space: pre",
+                (self.font_size)(),
+                (self.font_family)(),
+                if (self.bold)() { "bold" } else { "normal" },
+                if (self.italic)() { "italic" } else { "normal" },
+            )
+        }
+    }
+
+    /// the css font shorthand, for measuring
+    fn font(&self) -> String {
+        format!(
+            "{} {} {}px {}",
+            if (self.italic)() { "italic" } else { "normal" },
+            if (self.bold)() { "bold" } else { "normal" },
+            (self.font_size)() as f32 * EM,
+            (self.font_family)()
+        )
+    }
+
+    /// (left, top, right, bottom) in percent of the document, measured like the browser draws it
+    fn bounds(&self, doc: &DocumentSize) -> (f32, f32, f32, f32) {
+        let font = self.font();
+        let text = (self.text)();
+        let width = text
+            .split('\n')
+            .map(|line| text_width(&font, line))
+            .fold(0., f32::max);
+        let lines = text.split('\n').count() as f32;
+        let size = (self.font_size)() as f32 * EM;
+        let (width, em) = (width / doc.width * 100., size / doc.height * 100.);
+        let left = (self.x)() - width * (self.anchor)().offset();
+        let top = (self.y)() - em * ASCENT;
+        (left, top, left + width, top + em * LINE_HEIGHT * lines)
+    }
+
+    /// a caret after the first ``caret`` chars, laid out by drawing its line invisibly
+    pub fn caret_view(&self, caret: usize) -> leptos::View {
+        let (x, y, color, anchor) = (self.x, self.y, self.color, self.anchor);
+        let text = (self.text)();
+        let before: String = text.chars().take(caret).collect();
+        let row = before.matches('\n').count();
+        let line_before = before[before.rfind('\n').map_or(0, |i| i + 1)..].to_string();
+        let rest = &text[before.len()..];
+        let line_after = rest[..rest.find('\n').unwrap_or(rest.len())].to_string();
+        view! {
+            <text x={move || format_css(x())} y={move || format_css(y())} style={self.clone().style()} text-anchor={move || anchor().svg()}>
+                <tspan x={move || format_css(x())} dy={format!("{}em", row as f32 * LINE_HEIGHT)}>
+                    <tspan fill-opacity="0">{line_before}</tspan>
+                    <tspan class="caret" fill={color}>"|"</tspan>
+                    <tspan fill-opacity="0">{line_after}</tspan>
+                </tspan>
+            </text>
+        }
+        .into_view()
+    }
 }
 
 impl TrueSignalClone for Text {
@@ -433,16 +730,22 @@ impl TrueSignalClone for Text {
             text: RwSignal::new((self.text)()),
             font_size: RwSignal::new((self.font_size)()),
             color: RwSignal::new((self.color)()),
+            font_family: RwSignal::new((self.font_family)()),
+            bold: RwSignal::new((self.bold)()),
+            italic: RwSignal::new((self.italic)()),
+            anchor: RwSignal::new((self.anchor)()),
         }
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct Circle {
-    radius: RwSignal<u32>,
-    x: RwSignal<u32>,
-    y: RwSignal<u32>,
+    radius: RwSignal<f32>,
+    x: RwSignal<f32>,
+    y: RwSignal<f32>,
     color: RwSignal<String>,
+    /// drawn on the shape, empty for none
+    label: RwSignal<String>,
 }
 
 impl TrueSignalClone for Circle {
@@ -452,56 +755,81 @@ impl TrueSignalClone for Circle {
             x: RwSignal::new((self.x)()),
             y: RwSignal::new((self.y)()),
             color: RwSignal::new((self.color)()),
+            label: RwSignal::new((self.label)()),
         }
     }
 }
 
 impl Circle {
-    pub fn new(radius: u32, x: u32, y: u32) -> Self {
+    /// the radius is in units of the x axis, this is the same length on the y axis
+    fn ry(&self, r: f32) -> f32 {
+        let doc = use_context::<RwSignal<DocumentSize>>().unwrap();
+        doc.with(|doc| r * doc.width / doc.height)
+    }
+
+    fn label_pos(&self) -> (f32, f32, f32) {
+        ((self.x)(), (self.y)(), 0.)
+    }
+
+    pub fn new(radius: f32, x: f32, y: f32) -> Self {
         Self {
             radius: RwSignal::new(radius),
             x: RwSignal::new(x),
             y: RwSignal::new(y),
-            color: RwSignal::new("red".to_string()),
+            color: RwSignal::new(DEFAULT_COLOR.to_string()),
+            label: RwSignal::new(String::new()),
         }
     }
 }
 
 impl GraphicsItem for Circle {
     fn key(&self) -> u128 {
-        (self.radius)() as u128
+        (self.radius)().to_bits() as u128
     }
     fn get_overlay_dims(&self) -> SelectableOverlayData {
         let x = self.x.read_only();
         let y = self.y.read_only();
         let radius = self.radius.read_only();
+        let doc = use_context::<RwSignal<DocumentSize>>().unwrap();
+        let ry = move || doc.with(|doc| radius() * doc.width / doc.height);
 
-        let top = Signal::derive(move || x().checked_sub(radius()).unwrap_or(0));
-        let left = Signal::derive(move || y().checked_sub(radius()).unwrap_or(0));
         SelectableOverlayData::new(
-            top,
-            left,
-            Signal::derive(move || x() + radius() * 2),
-            Signal::derive(move || y() + radius() * 2),
+            Signal::derive(move || (y() - ry()).max(0.)),
+            Signal::derive(move || (x() - radius()).max(0.)),
+            Signal::derive(move || x() + radius()),
+            Signal::derive(move || y() + ry()),
         )
     }
     fn move_form(&self, coords: &Coords) {
-        match coords {
-            Coords::AbsCoord(x, y) => {
-                self.x.update(|c| *c += x);
-                self.y.update(|c| *c += y);
-            }
-            Coords::RelCoord(fcp) => {
-                let p1 = fcp.resolve_with_offset(((self.x)(), (self.y)()));
-                self.x.set(p1.0);
-                self.y.set(p1.1);
-            }
-        }
+        let (x, y, r) = ((self.x)(), (self.y)(), (self.radius)());
+        let ry = self.ry(r);
+        let (dx, dy) = clamp_movement(&[(x - r, y - ry), (x + r, y + ry)], coords.movement());
+        self.x.update(|c| *c += dx);
+        self.y.update(|c| *c += dy);
+    }
+    fn anchors(&self) -> Vec<(f32, f32)> {
+        let (x, y, r) = ((self.x)(), (self.y)(), (self.radius)());
+        let ry = self.ry(r);
+        vec![(x, y - ry), (x - r, y), (x, y), (x + r, y), (x, y + ry)]
+    }
+    fn set_color(&self, color: &str) {
+        self.color.set(color.to_string());
+    }
+    fn resize(&self, (hx, hy): Handle, (dx, dy): (f32, f32)) {
+        // the radius grows by how far the handle moves outwards, corners average both axes
+        let grow = (hx * dx + hy * dy) / (hx.abs() + hy.abs()).max(1.);
+        // the largest radius that keeps the circle inside of the canvas, like move_form does
+        let (x, y) = ((self.x)(), (self.y)());
+        let max = x
+            .min(CANVAS_SIZE - x)
+            .min(y.min(CANVAS_SIZE - y) / self.ry(1.))
+            .max(0.);
+        self.radius.update(|r| *r = (*r + grow).clamp(0., max));
     }
     fn find_collide(&self, veceq: &VectorEq) -> Option<f32> {
-        let x = (self.x)() as f32;
-        let y = (self.y)() as f32;
-        let r = (self.radius)() as f32;
+        let x = (self.x)();
+        let y = (self.y)();
+        let r = (self.radius)();
         let a = (veceq.start.0 - x, veceq.start.1 - y);
         let dot = a.0 * veceq.vec.0 + a.1 * veceq.vec.1;
         let a2 = a.0 * a.0 + a.1 * a.1;
@@ -526,51 +854,63 @@ impl GraphicsItem for Circle {
 impl GraphicsItem for Text {
     fn key(&self) -> u128 {
         let mut hasher = DefaultHasher::new();
-        ((((self.x)() as u128) << 32u128) + (self.y)() as u128).hash(&mut hasher);
+        ((((self.x)().to_bits() as u128) << 32u128) + (self.y)().to_bits() as u128)
+            .hash(&mut hasher);
         (self.text)().hash(&mut hasher);
         hasher.finish() as u128
     }
     fn get_overlay_dims(&self) -> SelectableOverlayData {
-        let font_size = self.font_size.read_only();
-        let text = self.text.read_only();
-        let x = self.x.read_only();
-        let y = self.y.read_only();
+        let doc = use_context::<RwSignal<DocumentSize>>().unwrap();
+        let bounds = {
+            let text = self.clone();
+            move || text.bounds(&doc())
+        };
+        let (top, left, right) = (bounds.clone(), bounds.clone(), bounds.clone());
         SelectableOverlayData::new(
-            self.x.into(),
-            self.y.into(),
-            // which idiot thought adding two random and barely related values with
-            // completely different units would've been a good idea? Oh, wait...
-            Signal::derive(move || x() + font_size() * text().len() as u32),
-            Signal::derive(move || y() + font_size()),
+            Signal::derive(move || top().1),
+            Signal::derive(move || left().0),
+            Signal::derive(move || right().2),
+            Signal::derive(move || bounds().3),
         )
     }
     fn move_form(&self, coords: &Coords) {
-        match coords {
-            Coords::AbsCoord(x, y) => {
-                self.x.update(|c| *c += x);
-                self.y.update(|c| *c += y);
-            }
-            Coords::RelCoord(fcp) => {
-                let p1 = fcp.resolve_with_offset(((self.x)(), (self.y)()));
-                self.x.set(p1.0);
-                self.y.set(p1.1);
-            }
-        }
+        let (dx, dy) = clamp_movement(&[((self.x)(), (self.y)())], coords.movement());
+        self.x.update(|c| *c += dx);
+        self.y.update(|c| *c += dy);
     }
+    fn anchors(&self) -> Vec<(f32, f32)> {
+        let doc = use_context::<RwSignal<DocumentSize>>().unwrap()();
+        let (left, top, right, bottom) = self.bounds(&doc);
+        let mut ret = vec![((self.x)(), (self.y)())];
+        ret.extend(box_anchors(left, top, right, bottom));
+        ret
+    }
+    fn set_color(&self, color: &str) {
+        self.color.set(color.to_string());
+    }
+    /// the size of texts comes from their font size, see ``:change font=``
+    fn resize(&self, _handle: Handle, _by: (f32, f32)) {}
     fn find_collide(&self, _veceq: &VectorEq) -> Option<f32> {
         None
     }
 }
 
-const DEFAULT_STYLE: &str = ";stroke-width=2";
-
 impl IntoView for Line {
     fn into_view(self) -> leptos::View {
         logging::log!("called into_view() on Line");
         let (x1, y1, x2, y2) = self.css_coords_reactive();
-        let style = move || format!("stroke:{}{}", (self.color)(), DEFAULT_STYLE);
+        let style = move || {
+            format!(
+                "stroke:{};stroke-width:{}",
+                (self.color)(),
+                (self.stroke_width)()
+            )
+        };
+        let this = self.clone();
+        let label = label_view(self.label, true, move |doc| this.label_pos(doc));
         view! {
             <line x1={x1} y1={y1} x2={x2} y2={y2} style={style}/>
+            {label}
         }
         .into_view()
     }
@@ -586,8 +926,11 @@ impl IntoView for Rect {
             width(),
             height()
         );
+        let this = self.clone();
+        let label = label_view(self.label, false, move |_| this.label_pos());
         view! {
-            <rect x={x} y={y} rx={self.rx} ry={self.ry} width={width} height={height} fill={self.inner_color}/>
+            <rect x={x} y={y} rx={self.rx} ry={self.ry} width={width} height={height} fill={move || fill::paint(&(self.inner_color)())}/>
+            {label}
         }
         .into_view()
     }
@@ -596,8 +939,21 @@ impl IntoView for Rect {
 impl IntoView for Text {
     fn into_view(self) -> leptos::View {
         let (x, y) = self.css_coords_reactive();
+        let x_signal = self.x;
+        // empty lines still need a char, or the next line would move up
+        let lines = move || {
+            (self.text)()
+                .split('\n')
+                .enumerate()
+                .map(|(i, line)| {
+                    let dy = if i == 0 { 0. } else { LINE_HEIGHT };
+                    let line = if line.is_empty() { " " } else { line }.to_string();
+                    view! { <tspan x={format_css(x_signal())} dy={format!("{dy}em")}>{line}</tspan> }
+                })
+                .collect_view()
+        };
         view! {
-            <text x={x} y={y} fill={self.color} style={move || format!("font-size: {}em", (self.font_size)())}>{self.text}</text>
+            <text x={x} y={y} fill={self.color} style={self.clone().style()} text-anchor={move || (self.anchor)().svg()}>{lines}</text>
         }
         .into_view()
     }
@@ -605,8 +961,12 @@ impl IntoView for Text {
 
 impl IntoView for Circle {
     fn into_view(self) -> leptos::View {
+        // percentages of r are relative to the diagonal, so the radius uses the same unit as x
+        let doc = use_context::<RwSignal<DocumentSize>>().unwrap();
+        let this = self.clone();
         view! {
-            <circle r={move || format_css((self.radius)())} cx={move || format_css((self.x)())} cy={move || format_css((self.y)())} fill={self.color}/>
+            <circle r={move || (self.radius)() * doc().width / 100.} cx={move || format_css((self.x)())} cy={move || format_css((self.y)())} fill={move || fill::paint(&(self.color)())}/>
+            {label_view(self.label, false, move |_| this.label_pos())}
         }
         .into_view()
     }
@@ -617,13 +977,16 @@ impl TryFrom<Command> for Line {
     fn try_from(value: Command) -> Result<Self, Self::Error> {
         if let CommandType::Line = value.ctype() {
             let ((x, y), (x2, y2)) = (value.start_coords(), value.coords().resolve());
-            let color = value.color().unwrap_or("red".to_string());
+            let settings = settings();
+            let color = value.color().unwrap_or(settings.color());
             Ok(Line {
                 x1: RwSignal::new(x),
                 y1: RwSignal::new(y),
                 x2: RwSignal::new(x2),
                 y2: RwSignal::new(y2),
                 color: RwSignal::new(color),
+                stroke_width: RwSignal::new(settings.stroke_width()),
+                label: RwSignal::new(String::new()),
             })
         } else {
             Err(value.ctype())
@@ -635,27 +998,29 @@ impl TryFrom<Command> for Rect {
     type Error = CommandType;
     fn try_from(command: Command) -> Result<Self, Self::Error> {
         if let CommandType::Rectangle = command.ctype() {
-            let color = command.color().unwrap_or("red".to_string());
+            let color = command.color().unwrap_or(settings().color());
+            let (rx, ry) = command.corners().unwrap_or_default();
             let ((mut x, mut y), (x2, y2)) = (command.start_coords(), command.coords().resolve());
-            let mut width: i32 = x2 as i32 - x as i32;
-            let mut height = y2 as i32 - y as i32;
-            if width < 0 {
-                x = (x as i32 + width) as u32;
+            let mut width = x2 - x;
+            let mut height = y2 - y;
+            if width < 0. {
+                x += width;
                 width = -width;
             }
-            if height < 0 {
-                y = (y as i32 + height) as u32;
+            if height < 0. {
+                y += height;
                 height = -height;
             }
             Ok(Self {
                 x: RwSignal::new(x),
                 y: RwSignal::new(y),
-                width: RwSignal::new(width as u32), // if this underflows, we're cooked
-                height: RwSignal::new(height as u32), // if this underflows, we're cooked
-                rx: RwSignal::new(Default::default()),
-                ry: RwSignal::new(Default::default()),
+                width: RwSignal::new(width),
+                height: RwSignal::new(height),
+                rx: RwSignal::new(rx),
+                ry: RwSignal::new(ry),
                 border_color: RwSignal::new(Default::default()),
                 inner_color: RwSignal::new(color),
+                label: RwSignal::new(String::new()),
             })
         } else {
             Err(command.ctype())
@@ -668,27 +1033,20 @@ impl TryFrom<Command> for Text {
     fn try_from(command: Command) -> Result<Self, Self::Error> {
         match command.ctype() {
             CommandType::Text => {
-                let text = loop {
-                    match window().prompt_with_message_and_default("Text:", LOREM_IPSUM) {
-                        Ok(text) => match text {
-                            Some(text) => break text,
-                            None => {
-                                window()
-                                    .alert_with_message("You gotta put something in there!")
-                                    .unwrap();
-                            }
-                        },
-                        Err(jsval) => logging::warn!("User's fault: {jsval:?} (should be null)"),
-                    }
-                };
                 let (x, y) = command.coords().resolve();
-                let color = command.color().unwrap_or("red".to_string());
+                let settings = settings();
+                let color = command.color().unwrap_or(settings.color());
                 Ok(Self {
                     x: x.into(),
                     y: y.into(),
-                    text: text.into(),
-                    font_size: RwSignal::new(1),
+                    // gets typed in insert mode
+                    text: RwSignal::new(String::new()),
+                    font_size: RwSignal::new(settings.font_size()),
                     color: color.into(),
+                    font_family: RwSignal::new(settings.font_family()),
+                    bold: RwSignal::new(settings.bold()),
+                    italic: RwSignal::new(settings.italic()),
+                    anchor: RwSignal::new(settings.anchor()),
                 })
             }
             other => Err(other),
@@ -702,12 +1060,13 @@ impl TryFrom<Command> for Circle {
         match com.ctype() {
             CommandType::Circle(rad) => {
                 let (x, y) = com.coords().resolve();
-                let color = com.color().unwrap_or("red".to_string());
+                let color = com.color().unwrap_or(settings().color());
                 Ok(Self {
-                    radius: RwSignal::new(rad),
+                    radius: RwSignal::new(rad.value().abs()),
                     x: RwSignal::new(x),
                     y: RwSignal::new(y),
                     color: RwSignal::new(color),
+                    label: RwSignal::new(String::new()),
                 })
             }
             other => Err(other),
@@ -715,16 +1074,162 @@ impl TryFrom<Command> for Circle {
     }
 }
 
+impl Form {
+    /// the form itself and, for groups, every form inside of it (those come first),
+    /// since the children of a group get drawn as forms of their own
+    pub fn flatten(&self) -> Vec<Form> {
+        let mut ret = match self {
+            Self::Group(group) => group
+                .forms
+                .borrow()
+                .iter()
+                .flat_map(|form| form.flatten())
+                .collect(),
+            _ => Vec::new(),
+        };
+        ret.push(self.clone());
+        ret
+    }
+
+    /// the name used by the vimp format and ``:select``
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Line(_) => "line",
+            Self::Rect(_) => "rect",
+            Self::Circle(_) => "circle",
+            Self::Text(_) => "text",
+            Self::Group(_) => "group",
+        }
+    }
+
+    /// sets one of the properties ``:change`` knows about, Ok(false) if the form doesn't have it,
+    /// groups pass it on to their forms
+    pub fn set_property(&self, name: &str, value: &str) -> Result<bool, String> {
+        fn num<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+            value
+                .parse()
+                .map_err(|_| format!("Not a valid {name}: {value}"))
+        }
+        fn flag(name: &str, value: &str) -> Result<bool, String> {
+            match value {
+                "true" => Ok(true),
+                "false" => Ok(false),
+                _ => Err(format!("Not a valid {name}: {value} (true|false)")),
+            }
+        }
+        fn radius(name: &str, value: &str) -> Result<f32, String> {
+            num(name, value)
+                .ok()
+                .filter(|r: &f32| r.is_finite() && *r >= 0.)
+                .ok_or_else(|| format!("Not a valid {name}: {value}"))
+        }
+        match (self, name) {
+            (_, "color") => {
+                self.check_color(value)?;
+                self.set_color(value)
+            }
+            (Self::Rect(r), "fill") => {
+                Fill::parse(value)?;
+                r.inner_color.set(value.to_string())
+            }
+            (Self::Circle(c), "fill") => {
+                Fill::parse(value)?;
+                c.color.set(value.to_string())
+            }
+            (Self::Line(l), "stroke") => l.stroke_width.set(num(name, value)?),
+            (Self::Text(t), "font") => t.font_size.set(num(name, value)?),
+            (Self::Text(t), "text") => t.text.set(value.to_string()),
+            (Self::Text(t), "family") => t.font_family.set(value.to_string()),
+            (Self::Text(t), "bold") => t.bold.set(flag(name, value)?),
+            (Self::Text(t), "italic") => t.italic.set(flag(name, value)?),
+            (Self::Text(t), "anchor") => t.anchor.set(value.parse()?),
+            (Self::Rect(r), "rx") => r.rx.set(radius(name, value)?),
+            (Self::Rect(r), "ry") => r.ry.set(radius(name, value)?),
+            (Self::Line(l), "label") => l.label.set(value.to_string()),
+            (Self::Rect(r), "label") => r.label.set(value.to_string()),
+            (Self::Circle(c), "label") => c.label.set(value.to_string()),
+            // every member would get the same one
+            (Self::Group(_), "label") => return Ok(false),
+            (Self::Group(g), _) => {
+                let mut changed = false;
+                for form in &*g.forms.borrow() {
+                    changed |= form.set_property(name, value)?;
+                }
+                return Ok(changed);
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// whether both are the same form (not only equal ones), copies have signals of their own
+    pub fn is(&self, other: &Form) -> bool {
+        match (self, other) {
+            (Self::Line(a), Self::Line(b)) => a.x1 == b.x1,
+            (Self::Rect(a), Self::Rect(b)) => a.x == b.x,
+            (Self::Circle(a), Self::Circle(b)) => a.x == b.x,
+            (Self::Text(a), Self::Text(b)) => a.x == b.x,
+            (Self::Group(a), Self::Group(b)) => Rc::ptr_eq(&a.forms, &b.forms),
+            _ => false,
+        }
+    }
+
+    /// whether set_color would work with this color, gradients and hatches only fill rects and
+    /// circles (also inside of groups)
+    pub fn check_color(&self, color: &str) -> Result<(), String> {
+        self.flatten().iter().try_for_each(|form| match form {
+            Self::Rect(_) | Self::Circle(_) | Self::Group(_) => Fill::parse(color).map(|_| ()),
+            Self::Line(_) | Self::Text(_) => plain_color(color),
+        })
+    }
+
+    /// what set_color sets, a group only has one if all of its forms share it
+    pub fn color(&self) -> Option<String> {
+        match self {
+            Self::Line(l) => Some((l.color)()),
+            Self::Rect(r) => Some((r.inner_color)()),
+            Self::Circle(c) => Some((c.color)()),
+            Self::Text(t) => Some((t.color)()),
+            Self::Group(g) => {
+                let colors: Vec<_> = g.forms.borrow().iter().map(|form| form.color()).collect();
+                match colors.first() {
+                    Some(first) if colors.iter().all(|color| color == first) => first.clone(),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+/// the forms that aren't inside of one of the groups, the groups bring them along anyway
+pub fn top_level(forms: &[Form]) -> Vec<Form> {
+    let members: Vec<Form> = forms
+        .iter()
+        .filter(|form| matches!(form, Form::Group(_)))
+        .flat_map(|group| {
+            let mut members = group.flatten();
+            // flatten puts the group itself last
+            members.pop();
+            members
+        })
+        .collect();
+    forms
+        .iter()
+        .filter(|form| !members.iter().any(|member| member.is(form)))
+        .cloned()
+        .collect()
+}
+
 #[derive(Clone, Debug)]
 pub struct Group {
     forms: Rc<RefCell<Vec<Form>>>,
-    left: Signal<u32>,
-    top: Signal<u32>,
-    width: Signal<u32>,
-    height: Signal<u32>,
+    left: Signal<f32>,
+    top: Signal<f32>,
+    width: Signal<f32>,
+    height: Signal<f32>,
 }
 
-fn format_css_signal(signal: Signal<u32>) -> Signal<String> {
+fn format_css_signal(signal: Signal<f32>) -> Signal<String> {
     Signal::derive(move || format_css((signal)()))
 }
 
@@ -750,10 +1255,10 @@ impl FromIterator<Form> for Group {
     fn from_iter<T: IntoIterator<Item = Form>>(iter: T) -> Self {
         let mut ret = Group {
             forms: Rc::new(RefCell::new(Vec::with_capacity(3))),
-            left: Signal::derive(|| 0),
-            top: Signal::derive(|| 0),
-            width: Signal::derive(|| 0),
-            height: Signal::derive(|| 0),
+            left: Signal::derive(|| 0.),
+            top: Signal::derive(|| 0.),
+            width: Signal::derive(|| 0.),
+            height: Signal::derive(|| 0.),
         };
         for form in iter {
             ret.forms.borrow_mut().push(form);
@@ -764,8 +1269,8 @@ impl FromIterator<Form> for Group {
                 .iter()
                 .map(|f| f.get_overlay_dims())
                 .map(|sod| sod.left())
-                .min()
-                .unwrap_or(0)
+                .reduce(f32::min)
+                .unwrap_or(0.)
         });
         let tmp = Rc::clone(&ret.forms);
         ret.top = Signal::derive(move || {
@@ -773,8 +1278,8 @@ impl FromIterator<Form> for Group {
                 .iter()
                 .map(|f| f.get_overlay_dims())
                 .map(|sod| sod.top())
-                .min()
-                .unwrap_or(0)
+                .reduce(f32::min)
+                .unwrap_or(0.)
         });
         let tmp = Rc::clone(&ret.forms);
         ret.width = Signal::derive(move || {
@@ -782,8 +1287,8 @@ impl FromIterator<Form> for Group {
                 .iter()
                 .map(|f| f.get_overlay_dims())
                 .map(|sod| sod.end_x())
-                .max()
-                .unwrap_or(100)
+                .reduce(f32::max)
+                .unwrap_or(100.)
         });
         let tmp = Rc::clone(&ret.forms);
         ret.height = Signal::derive(move || {
@@ -791,8 +1296,8 @@ impl FromIterator<Form> for Group {
                 .iter()
                 .map(|f| f.get_overlay_dims())
                 .map(|sod| sod.end_y())
-                .max()
-                .unwrap_or(100)
+                .reduce(f32::max)
+                .unwrap_or(100.)
         });
         ret
     }
@@ -807,13 +1312,31 @@ impl GraphicsItem for Group {
         hasher.finish() as u128
     }
     fn move_form(&self, coords: &Coords) {
+        // clamp with the bounds of the whole group, so it keeps its shape at the border
+        let (dx, dy) = clamp_movement(
+            &[
+                ((self.left)(), (self.top)()),
+                ((self.width)(), (self.height)()),
+            ],
+            coords.movement(),
+        );
         for form in &*self.forms.borrow() {
-            form.move_form(coords);
+            form.move_form(&Coords::AbsCoord(dx, dy));
         }
     }
+    fn anchors(&self) -> Vec<(f32, f32)> {
+        box_anchors((self.left)(), (self.top)(), (self.width)(), (self.height)())
+    }
     fn get_overlay_dims(&self) -> SelectableOverlayData {
         SelectableOverlayData::new(self.top, self.left, self.width, self.height)
     }
+    fn set_color(&self, color: &str) {
+        for form in &*self.forms.borrow() {
+            form.set_color(color);
+        }
+    }
+    /// groups can only be moved as a whole
+    fn resize(&self, _handle: Handle, _by: (f32, f32)) {}
     fn find_collide(&self, _veceq: &VectorEq) -> Option<f32> {
         None
     }
@@ -827,20 +1350,12 @@ impl TrueSignalClone for Group {
             .iter()
             .map(|form| form.deep_clone())
             .collect();
-        let set_forms = use_context::<FormsWS>().unwrap().0;
-        let set_overlays = use_context::<OverlaysWS>().unwrap().0;
-        set_overlays.update(|vec| {
-            forms
-                .iter()
-                .for_each(|form| vec.push(form.get_overlay_dims()))
-        });
-        set_forms.update(|vec| forms.iter().for_each(|form| vec.push(form.clone())));
         let mut ret = Self {
             forms: Rc::new(RefCell::new(forms)),
-            left: Signal::derive(|| 0),
-            top: Signal::derive(|| 0),
-            width: Signal::derive(|| 0),
-            height: Signal::derive(|| 0),
+            left: Signal::derive(|| 0.),
+            top: Signal::derive(|| 0.),
+            width: Signal::derive(|| 0.),
+            height: Signal::derive(|| 0.),
         };
         let tmp = Rc::clone(&ret.forms);
         ret.left = Signal::derive(move || {
@@ -848,8 +1363,8 @@ impl TrueSignalClone for Group {
                 .iter()
                 .map(|f| f.get_overlay_dims())
                 .map(|sod| sod.left())
-                .min()
-                .unwrap_or(0)
+                .reduce(f32::min)
+                .unwrap_or(0.)
         });
         let tmp = Rc::clone(&ret.forms);
         ret.top = Signal::derive(move || {
@@ -857,8 +1372,8 @@ impl TrueSignalClone for Group {
                 .iter()
                 .map(|f| f.get_overlay_dims())
                 .map(|sod| sod.top())
-                .min()
-                .unwrap_or(0)
+                .reduce(f32::min)
+                .unwrap_or(0.)
         });
         let tmp = Rc::clone(&ret.forms);
         ret.width = Signal::derive(move || {
@@ -866,8 +1381,8 @@ impl TrueSignalClone for Group {
                 .iter()
                 .map(|f| f.get_overlay_dims())
                 .map(|sod| sod.end_x())
-                .max()
-                .unwrap_or(100)
+                .reduce(f32::max)
+                .unwrap_or(100.)
         });
         let tmp = Rc::clone(&ret.forms);
         ret.height = Signal::derive(move || {
@@ -875,8 +1390,8 @@ impl TrueSignalClone for Group {
                 .iter()
                 .map(|f| f.get_overlay_dims())
                 .map(|sod| sod.end_y())
-                .max()
-                .unwrap_or(100)
+                .reduce(f32::max)
+                .unwrap_or(100.)
         });
 
         ret
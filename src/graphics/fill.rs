@@ -0,0 +1,180 @@
+use std::collections::HashSet;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use super::serialize::escape_xml;
+use super::Form;
+use crate::components::DocumentSize;
+
+/// fills of rects and circles that aren't plain colors, they are written where a color would go:
+/// ``lin(<angle>,<color>,<color>...)``, ``rad(<color>,<color>...)`` and
+/// ``hatch(<color>[,<angle>[,<spacing>]])``
+#[derive(Debug, Clone)]
+pub enum Fill {
+    Linear {
+        angle: f32,
+        stops: Vec<String>,
+    },
+    Radial {
+        stops: Vec<String>,
+    },
+    /// the spacing is in percent of the document width
+    Hatch {
+        color: String,
+        angle: f32,
+        spacing: f32,
+    },
+}
+
+/// splits at the commas that aren't inside of parentheses, so ``rgb(1,2,3)`` stays one color
+fn args(str: &str) -> Vec<&str> {
+    let mut ret = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in str.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                ret.push(str[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    ret.push(str[start..].trim());
+    ret
+}
+
+fn num(name: &str, value: &str) -> Result<f32, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Not a valid {name}: {value}"))
+}
+
+impl Fill {
+    /// None for plain colors
+    pub fn parse(str: &str) -> Result<Option<Self>, String> {
+        let Some((kind, rest)) = str.trim().split_once('(') else {
+            return Ok(None);
+        };
+        let Some(rest) = rest.strip_suffix(')') else {
+            return Ok(None);
+        };
+        let args = args(rest);
+        let stops = |colors: &[&str]| -> Result<Vec<String>, String> {
+            if colors.len() < 2 || colors.iter().any(|color| color.is_empty()) {
+                return Err(format!("{kind}() needs at least two colors"));
+            }
+            Ok(colors.iter().map(|color| color.to_string()).collect())
+        };
+        Ok(Some(match kind {
+            "lin" => Self::Linear {
+                angle: num("angle", args[0])?,
+                stops: stops(&args[1..])?,
+            },
+            "rad" => Self::Radial {
+                stops: stops(&args)?,
+            },
+            "hatch" => {
+                if args.len() > 3 || args[0].is_empty() {
+                    return Err("Usage: hatch(<color>[,<angle>[,<spacing>]])".to_string());
+                }
+                let spacing = args.get(2).map_or(Ok(1.), |s| num("spacing", s))?;
+                if spacing <= 0. {
+                    return Err(format!("Not a valid spacing: {spacing}"));
+                }
+                Self::Hatch {
+                    color: args[0].to_string(),
+                    angle: args.get(1).map_or(Ok(45.), |a| num("angle", a))?,
+                    spacing,
+                }
+            }
+            // rgb(...) and friends
+            _ => return Ok(None),
+        }))
+    }
+
+    /// the same fill always gets the same id, so forms can share their defs
+    fn id(spec: &str) -> String {
+        let mut hasher = DefaultHasher::new();
+        spec.hash(&mut hasher);
+        format!("vimp-fill-{:x}", hasher.finish())
+    }
+
+    fn stops_to_svg(stops: &[String]) -> String {
+        stops
+            .iter()
+            .enumerate()
+            .map(|(i, color)| {
+                format!(
+                    "<stop offset=\"{}%\" stop-color=\"{}\"/>",
+                    i as f32 * 100. / (stops.len() - 1) as f32,
+                    escape_xml(color)
+                )
+            })
+            .collect()
+    }
+
+    fn to_svg(&self, id: &str, doc: &DocumentSize) -> String {
+        match self {
+            Self::Linear { angle, stops } => format!(
+                "<linearGradient id=\"{id}\" gradientTransform=\"rotate({angle} 0.5 0.5)\">{}</linearGradient>",
+                Self::stops_to_svg(stops)
+            ),
+            Self::Radial { stops } => format!(
+                "<radialGradient id=\"{id}\">{}</radialGradient>",
+                Self::stops_to_svg(stops)
+            ),
+            Self::Hatch {
+                color,
+                angle,
+                spacing,
+            } => {
+                let size = spacing * doc.width / 100.;
+                format!(
+                    "<pattern id=\"{id}\" patternUnits=\"userSpaceOnUse\" width=\"{size}\" height=\"{size}\" patternTransform=\"rotate({angle})\">\
+<line x1=\"{}\" y1=\"0\" x2=\"{}\" y2=\"{size}\" stroke=\"{}\" stroke-width=\"{}\"/></pattern>",
+                    size / 2.,
+                    size / 2.,
+                    escape_xml(color),
+                    size / 4.
+                )
+            }
+        }
+    }
+}
+
+/// lines and texts have no defs for their stroke and fill, so they only take plain colors
+pub fn plain_color(color: &str) -> Result<(), String> {
+    match Fill::parse(color)? {
+        Some(_) => Err(format!("Only rects and circles can be filled with {color}")),
+        None => Ok(()),
+    }
+}
+
+/// what goes into the fill attribute, the defs of the drawing have to contain the fill
+pub fn paint(color: &str) -> String {
+    match Fill::parse(color) {
+        Ok(Some(_)) => format!("url(#{})", Fill::id(color)),
+        _ => color.to_string(),
+    }
+}
+
+/// the gradients and patterns used by the forms (and the forms inside their groups)
+pub fn fill_defs(forms: &[Form], doc: &DocumentSize) -> String {
+    let mut seen = HashSet::new();
+    forms
+        .iter()
+        .flat_map(|form| form.flatten())
+        .filter_map(|form| match form {
+            Form::Rect(r) => Some((r.inner_color)()),
+            Form::Circle(c) => Some((c.color)()),
+            _ => None,
+        })
+        .filter_map(|spec| {
+            let fill = Fill::parse(&spec).ok()??;
+            let id = Fill::id(&spec);
+            seen.insert(id.clone()).then(|| fill.to_svg(&id, doc))
+        })
+        .collect()
+}
@@ -0,0 +1,577 @@
+use leptos::web_sys::{DomParser, Element, SupportedType};
+use leptos::RwSignal;
+
+use super::fill::{self, fill_defs};
+use super::LINE_HEIGHT;
+use super::{Circle, Form, Group, Line, Rect, Text, TextAnchor};
+use crate::components::DocumentSize;
+use crate::settings::settings;
+
+/// first line of every drawing in vimp's own format, followed by the version
+pub const VIMP_HEADER: &str = "vimp";
+const VIMP_VERSION: u32 = 1;
+
+pub(super) fn escape_xml(str: &str) -> String {
+    str.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_line(str: &str) -> String {
+    str.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+/// for fields that have to stay one word, spaces become ``\s``
+fn escape_word(str: &str) -> String {
+    escape_line(str).replace(' ', "\\s")
+}
+
+fn unescape_line(str: &str) -> String {
+    let mut ret = String::with_capacity(str.len());
+    let mut chars = str.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            ret.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => ret.push('\n'),
+            Some('s') => ret.push(' '),
+            Some(c) => ret.push(c),
+            None => ret.push('\\'),
+        }
+    }
+    ret
+}
+
+/// colors are single words in the vimp format (``rgb(1, 2, 3)`` becomes ``rgb(1,2,3)``)
+fn one_word(color: &str) -> String {
+    color.split_whitespace().collect()
+}
+
+/// the label of a shape is whatever comes after its color
+fn with_label(line: String, label: &str) -> String {
+    if label.is_empty() {
+        line
+    } else {
+        format!("{line} {}", escape_line(label))
+    }
+}
+
+/// one form per line, the forms of a group are indented between ``group`` and ``end``
+pub fn to_vimp(forms: &[Form]) -> String {
+    fn write(form: &Form, indent: usize, out: &mut String) {
+        let pad = "  ".repeat(indent);
+        let line = match form {
+            Form::Line(l) => with_label(
+                format!(
+                    "line {} {} {} {} {} {}",
+                    (l.x1)(),
+                    (l.y1)(),
+                    (l.x2)(),
+                    (l.y2)(),
+                    (l.stroke_width)(),
+                    one_word(&(l.color)())
+                ),
+                &(l.label)(),
+            ),
+            Form::Rect(r) => with_label(
+                format!(
+                    "rect {} {} {} {} {} {} {}",
+                    (r.x)(),
+                    (r.y)(),
+                    (r.width)(),
+                    (r.height)(),
+                    (r.rx)(),
+                    (r.ry)(),
+                    one_word(&(r.inner_color)())
+                ),
+                &(r.label)(),
+            ),
+            Form::Circle(c) => with_label(
+                format!(
+                    "circle {} {} {} {}",
+                    (c.x)(),
+                    (c.y)(),
+                    (c.radius)(),
+                    one_word(&(c.color)())
+                ),
+                &(c.label)(),
+            ),
+            Form::Text(t) => format!(
+                "text {} {} {} {} {} {}{} {} {}",
+                (t.x)(),
+                (t.y)(),
+                (t.font_size)(),
+                one_word(&(t.color)()),
+                (t.anchor)(),
+                if (t.bold)() { 'b' } else { '-' },
+                if (t.italic)() { 'i' } else { '-' },
+                escape_word(&(t.font_family)()),
+                escape_line(&(t.text)())
+            ),
+            Form::Group(g) => {
+                out.push_str(&format!("{pad}group\n"));
+                g.forms
+                    .borrow()
+                    .iter()
+                    .for_each(|form| write(form, indent + 1, out));
+                "end".to_string()
+            }
+        };
+        out.push_str(&format!("{pad}{line}\n"));
+    }
+    let mut ret = format!("{VIMP_HEADER} {VIMP_VERSION}\n");
+    forms.iter().for_each(|form| write(form, 0, &mut ret));
+    ret
+}
+
+/// the forms of groups only live inside of them, use Form::flatten to get every form
+pub fn from_vimp(str: &str) -> Result<Vec<Form>, String> {
+    let mut lines = str
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()));
+    match lines.next().and_then(|(_, line)| line.split_once(' ')) {
+        Some((VIMP_HEADER, version)) if version.parse() == Ok(VIMP_VERSION) => {}
+        Some((VIMP_HEADER, version)) => {
+            return Err(format!("Unknown version of the vimp format: {version}"))
+        }
+        _ => return Err(format!("Not a vimp drawing (missing \"{VIMP_HEADER}\")")),
+    }
+    // the forms of every group that hasn't ended yet, the drawing itself is at the bottom
+    let mut stack: Vec<Vec<Form>> = vec![Vec::new()];
+    for (nr, line) in lines.filter(|(_, line)| !line.is_empty()) {
+        let err = |msg: &str| format!("line {nr}: {msg}");
+        let (kind, args) = line.split_once(' ').unwrap_or((line, ""));
+        let nums = |n: usize| -> Result<Vec<f32>, String> {
+            let nums = args
+                .split_whitespace()
+                .take(n)
+                .map(|num| num.parse::<f32>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| err("invalid number"))?;
+            if nums.len() < n {
+                return Err(err(&format!("{kind} needs {n} numbers")));
+            }
+            Ok(nums)
+        };
+        // whatever comes after the first n words
+        let rest = |n: usize| args.splitn(n + 1, ' ').nth(n).unwrap_or("").to_string();
+        let word = |n: usize| args.split(' ').nth(n).unwrap_or("").to_string();
+        let form = match kind {
+            "line" => {
+                let n = nums(5)?;
+                Form::Line(Line {
+                    x1: RwSignal::new(n[0]),
+                    y1: RwSignal::new(n[1]),
+                    x2: RwSignal::new(n[2]),
+                    y2: RwSignal::new(n[3]),
+                    stroke_width: RwSignal::new(n[4]),
+                    color: RwSignal::new(word(5)),
+                    label: RwSignal::new(unescape_line(&rest(6))),
+                })
+            }
+            "rect" => {
+                let n = nums(6)?;
+                Form::Rect(Rect {
+                    x: RwSignal::new(n[0]),
+                    y: RwSignal::new(n[1]),
+                    width: RwSignal::new(n[2]),
+                    height: RwSignal::new(n[3]),
+                    rx: RwSignal::new(n[4]),
+                    ry: RwSignal::new(n[5]),
+                    border_color: RwSignal::new(Default::default()),
+                    inner_color: RwSignal::new(word(6)),
+                    label: RwSignal::new(unescape_line(&rest(7))),
+                })
+            }
+            "circle" => {
+                let n = nums(3)?;
+                Form::Circle(Circle {
+                    x: RwSignal::new(n[0]),
+                    y: RwSignal::new(n[1]),
+                    radius: RwSignal::new(n[2]),
+                    color: RwSignal::new(word(3)),
+                    label: RwSignal::new(unescape_line(&rest(4))),
+                })
+            }
+            "text" => {
+                let n = nums(3)?;
+                let rest = rest(3);
+                let mut words = rest.splitn(5, ' ');
+                let mut word = || words.next().unwrap_or("");
+                let color = word().to_string();
+                let anchor = word().parse().map_err(|e: String| err(&e))?;
+                let style = word();
+                let family = unescape_line(word());
+                Form::Text(Text {
+                    x: RwSignal::new(n[0]),
+                    y: RwSignal::new(n[1]),
+                    font_size: RwSignal::new(n[2] as u32),
+                    color: RwSignal::new(color),
+                    font_family: RwSignal::new(family),
+                    bold: RwSignal::new(style.contains('b')),
+                    italic: RwSignal::new(style.contains('i')),
+                    anchor: RwSignal::new(anchor),
+                    text: RwSignal::new(unescape_line(word())),
+                })
+            }
+            "group" => {
+                stack.push(Vec::new());
+                continue;
+            }
+            "end" if stack.len() > 1 => {
+                let forms = stack.pop().unwrap();
+                Form::Group(Group::from_iter(forms))
+            }
+            other => return Err(err(&format!("unknown form \"{other}\""))),
+        };
+        stack.last_mut().unwrap().push(form);
+    }
+    if stack.len() > 1 {
+        return Err("group without end".to_string());
+    }
+    Ok(stack.pop().unwrap())
+}
+
+/// written right after the shape, so it ends up on top of it like on the canvas
+fn label_to_svg(
+    label: &str,
+    along: bool,
+    (x, y, angle): (f32, f32, f32),
+    size: &DocumentSize,
+) -> String {
+    if label.is_empty() {
+        return String::new();
+    }
+    let (baseline, dy) = if along {
+        ("auto", "-0.3em")
+    } else {
+        ("middle", "0")
+    };
+    format!(
+        "<text x=\"{x}%\" y=\"{y}%\" dy=\"{dy}\" transform=\"rotate({angle} {} {})\" text-anchor=\"middle\" dominant-baseline=\"{baseline}\" fill=\"black\" stroke=\"white\" stroke-width=\"3\" paint-order=\"stroke\">{}</text>",
+        x * size.width / 100.,
+        y * size.height / 100.,
+        escape_xml(label)
+    )
+}
+
+fn form_to_svg(form: &Form, size: &DocumentSize) -> String {
+    let label = match form {
+        Form::Line(l) => label_to_svg(&(l.label)(), true, l.label_pos(size), size),
+        Form::Rect(r) => label_to_svg(&(r.label)(), false, r.label_pos(), size),
+        Form::Circle(c) => label_to_svg(&(c.label)(), false, c.label_pos(), size),
+        _ => String::new(),
+    };
+    let shape = match form {
+        Form::Line(l) => format!(
+            "<line x1=\"{}%\" y1=\"{}%\" x2=\"{}%\" y2=\"{}%\" style=\"stroke:{};stroke-width:{}\"/>",
+            (l.x1)(),
+            (l.y1)(),
+            (l.x2)(),
+            (l.y2)(),
+            escape_xml(&(l.color)()),
+            (l.stroke_width)()
+        ),
+        Form::Rect(r) => format!(
+            "<rect x=\"{}%\" y=\"{}%\" rx=\"{}\" ry=\"{}\" width=\"{}%\" height=\"{}%\" fill=\"{}\"/>",
+            (r.x)(),
+            (r.y)(),
+            (r.rx)(),
+            (r.ry)(),
+            (r.width)(),
+            (r.height)(),
+            escape_xml(&fill::paint(&(r.inner_color)()))
+        ),
+        Form::Circle(c) => format!(
+            "<circle r=\"{}\" cx=\"{}%\" cy=\"{}%\" fill=\"{}\"/>",
+            (c.radius)() * size.width / 100.,
+            (c.x)(),
+            (c.y)(),
+            escape_xml(&fill::paint(&(c.color)()))
+        ),
+        Form::Text(t) => {
+            let lines: String = (t.text)()
+                .split('\n')
+                .enumerate()
+                .map(|(i, line)| {
+                    format!(
+                        "<tspan x=\"{}%\" dy=\"{}em\">{}</tspan>",
+                        (t.x)(),
+                        if i == 0 { 0. } else { LINE_HEIGHT },
+                        escape_xml(if line.is_empty() { " " } else { line })
+                    )
+                })
+                .collect();
+            format!(
+                "<text x=\"{}%\" y=\"{}%\" fill=\"{}\" text-anchor=\"{}\" style=\"font-size: {}em; font-family: {}; font-weight: {}; font-style: {}; white-space: pre\">{}</text>",
+                (t.x)(),
+                (t.y)(),
+                escape_xml(&(t.color)()),
+                (t.anchor)().svg(),
+                (t.font_size)(),
+                escape_xml(&(t.font_family)()),
+                if (t.bold)() { "bold" } else { "normal" },
+                if (t.italic)() { "italic" } else { "normal" },
+                lines
+            )
+        }
+        Form::Group(g) => {
+            let forms: String = g
+                .forms
+                .borrow()
+                .iter()
+                .map(|form| form_to_svg(form, size))
+                .collect();
+            format!("<g>{forms}</g>")
+        }
+    };
+    shape + &label
+}
+
+/// the forms in the vimp format, from_svg prefers it over the svg elements,
+/// so reading the svg back into vimp doesn't lose anything
+pub fn vimp_metadata(forms: &[Form]) -> String {
+    format!(
+        "<metadata id=\"vimp\">{}</metadata>",
+        escape_xml(&to_vimp(forms))
+    )
+}
+
+/// a standalone svg of forms, with the forms in its metadata
+pub fn to_svg(forms: &[Form], size: &DocumentSize) -> String {
+    let body: String = forms.iter().map(|form| form_to_svg(form, size)).collect();
+    format!(
+        "<svg width=\"{}{}\" height=\"{}{}\" viewBox=\"{}\" version=\"1.1\" xmlns=\"http://www.w3.org/2000/svg\">\
+{}<defs>{}</defs>{body}</svg>",
+        size.width,
+        size.unit,
+        size.height,
+        size.unit,
+        size.view_box(),
+        vimp_metadata(forms),
+        fill_defs(forms, size),
+    )
+}
+
+/// maps user units of the pasted svg to percentages of the document
+struct SvgUnits {
+    min: (f32, f32),
+    size: (f32, f32),
+}
+
+impl SvgUnits {
+    fn of(root: &Element, doc: &DocumentSize) -> Self {
+        let view_box: Vec<f32> = root
+            .get_attribute("viewBox")
+            .unwrap_or_default()
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter_map(|num| num.parse().ok())
+            .collect();
+        if let [x, y, w, h] = view_box[..]
+            && w > 0.
+            && h > 0.
+        {
+            return Self {
+                min: (x, y),
+                size: (w, h),
+            };
+        }
+        let length = |name: &str, default: f32| {
+            root.get_attribute(name)
+                .and_then(|val| leading_number(&val))
+                .unwrap_or(default)
+        };
+        Self {
+            min: (0., 0.),
+            size: (length("width", doc.width), length("height", doc.height)),
+        }
+    }
+
+    fn coord(&self, el: &Element, name: &str, horizontal: bool) -> f32 {
+        let (min, size) = if horizontal {
+            (self.min.0, self.size.0)
+        } else {
+            (self.min.1, self.size.1)
+        };
+        match el.get_attribute(name) {
+            Some(val) if val.trim_end().ends_with('%') => leading_number(&val).unwrap_or(0.),
+            Some(val) => (leading_number(&val).unwrap_or(0.) - min) / size * 100.,
+            None => 0.,
+        }
+    }
+
+    /// like coord, but for sizes, which don't care where the view box starts
+    fn length(&self, el: &Element, name: &str, horizontal: bool) -> f32 {
+        let size = if horizontal { self.size.0 } else { self.size.1 };
+        match el.get_attribute(name) {
+            Some(val) if val.trim_end().ends_with('%') => leading_number(&val).unwrap_or(0.),
+            Some(val) => leading_number(&val).unwrap_or(0.) / size * 100.,
+            None => 0.,
+        }
+    }
+}
+
+fn leading_number(str: &str) -> Option<f32> {
+    let str = str.trim();
+    let end = str
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-'))
+        .unwrap_or(str.len());
+    str[..end].parse().ok()
+}
+
+fn attribute_number(el: &Element, name: &str) -> f32 {
+    el.get_attribute(name)
+        .and_then(|val| leading_number(&val))
+        .unwrap_or(0.)
+}
+
+/// a presentation attribute, the style attribute wins like in css
+fn property(el: &Element, name: &str) -> Option<String> {
+    let style = el.get_attribute("style").unwrap_or_default();
+    style
+        .split(';')
+        .filter_map(|decl| decl.split_once(':'))
+        .find(|(prop, _)| prop.trim() == name)
+        .map(|(_, val)| val.trim().to_string())
+        .or_else(|| el.get_attribute(name))
+}
+
+fn paint(el: &Element, name: &str) -> String {
+    match property(el, name) {
+        Some(paint) if paint != "none" => paint,
+        _ => "black".to_string(),
+    }
+}
+
+/// every tspan that sets its own x starts a new line, like vimp writes them
+fn text_lines(el: &Element) -> String {
+    let children = el.children();
+    let tspans: Vec<_> = (0..children.length())
+        .filter_map(|i| children.item(i))
+        .filter(|child| child.local_name() == "tspan")
+        .collect();
+    if tspans.is_empty() {
+        return el.text_content().unwrap_or_default();
+    }
+    let mut ret = String::new();
+    for (i, tspan) in tspans.iter().enumerate() {
+        if i > 0 && tspan.has_attribute("x") {
+            ret.push('\n');
+        }
+        ret.push_str(&tspan.text_content().unwrap_or_default());
+    }
+    // empty lines got written as a space
+    ret.split('\n')
+        .map(|line| if line == " " { "" } else { line })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn parse_element(el: &Element, units: &SvgUnits, doc: &DocumentSize, out: &mut Vec<Form>) {
+    let form = match &*el.local_name() {
+        "line" => Form::Line(Line {
+            x1: RwSignal::new(units.coord(el, "x1", true)),
+            y1: RwSignal::new(units.coord(el, "y1", false)),
+            x2: RwSignal::new(units.coord(el, "x2", true)),
+            y2: RwSignal::new(units.coord(el, "y2", false)),
+            color: RwSignal::new(paint(el, "stroke")),
+            stroke_width: RwSignal::new(
+                property(el, "stroke-width")
+                    .and_then(|width| leading_number(&width))
+                    .map_or(1., |width| width * doc.width / units.size.0),
+            ),
+            label: RwSignal::new(String::new()),
+        }),
+        "rect" => Form::Rect(Rect {
+            x: RwSignal::new(units.coord(el, "x", true)),
+            y: RwSignal::new(units.coord(el, "y", false)),
+            width: RwSignal::new(units.length(el, "width", true)),
+            height: RwSignal::new(units.length(el, "height", false)),
+            rx: RwSignal::new(attribute_number(el, "rx")),
+            ry: RwSignal::new(attribute_number(el, "ry")),
+            border_color: RwSignal::new(Default::default()),
+            inner_color: RwSignal::new(paint(el, "fill")),
+            label: RwSignal::new(String::new()),
+        }),
+        "circle" => Form::Circle(Circle {
+            x: RwSignal::new(units.coord(el, "cx", true)),
+            y: RwSignal::new(units.coord(el, "cy", false)),
+            radius: RwSignal::new(units.length(el, "r", true)),
+            color: RwSignal::new(paint(el, "fill")),
+            label: RwSignal::new(String::new()),
+        }),
+        "text" => Form::Text(Text {
+            x: RwSignal::new(units.coord(el, "x", true)),
+            y: RwSignal::new(units.coord(el, "y", false)),
+            text: RwSignal::new(text_lines(el)),
+            font_family: RwSignal::new(
+                property(el, "font-family").unwrap_or_else(|| settings().font_family()),
+            ),
+            bold: RwSignal::new(property(el, "font-weight").is_some_and(|weight| {
+                weight == "bold" || weight == "bolder" || leading_number(&weight) >= Some(600.)
+            })),
+            italic: RwSignal::new(
+                property(el, "font-style").is_some_and(|style| style != "normal"),
+            ),
+            anchor: RwSignal::new(
+                property(el, "text-anchor")
+                    .and_then(|anchor| anchor.parse().ok())
+                    .unwrap_or(TextAnchor::Left),
+            ),
+            font_size: RwSignal::new(
+                property(el, "font-size")
+                    .filter(|size| size.ends_with("em"))
+                    .and_then(|size| leading_number(&size))
+                    .map_or(settings().font_size(), |size| size.max(1.) as u32),
+            ),
+            color: RwSignal::new(paint(el, "fill")),
+        }),
+        "g" | "svg" | "a" => {
+            let mut forms = Vec::new();
+            let children = el.children();
+            for i in 0..children.length() {
+                if let Some(child) = children.item(i) {
+                    parse_element(&child, units, doc, &mut forms);
+                }
+            }
+            if el.local_name() == "g" && forms.len() > 1 {
+                Form::Group(Group::from_iter(forms))
+            } else {
+                out.extend(forms);
+                return;
+            }
+        }
+        // paths, metadata, defs, ... aren't forms vimp knows
+        _ => return,
+    };
+    out.push(form);
+}
+
+/// svg written by vimp comes back exactly, anything else gets converted as far as vimp's forms go
+pub fn from_svg(str: &str, doc: &DocumentSize) -> Result<Vec<Form>, String> {
+    let parser = DomParser::new().map_err(|err| format!("{err:?}"))?;
+    let svg = parser
+        .parse_from_string(str, SupportedType::ImageSvgXml)
+        .map_err(|err| format!("{err:?}"))?;
+    if svg.get_elements_by_tag_name("parsererror").length() > 0 {
+        return Err("Invalid svg".to_string());
+    }
+    if let Some(vimp) = svg.get_element_by_id("vimp")
+        && let Some(text) = vimp.text_content()
+        && text.trim_start().starts_with(VIMP_HEADER)
+    {
+        return from_vimp(&text);
+    }
+    let root = svg.document_element().ok_or("Empty svg")?;
+    let units = SvgUnits::of(&root, doc);
+    let mut forms = Vec::new();
+    parse_element(&root, &units, doc, &mut forms);
+    if forms.is_empty() {
+        return Err("No lines, rects, circles or texts in the svg".to_string());
+    }
+    Ok(forms)
+}
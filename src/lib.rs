@@ -6,3 +6,4 @@
 pub mod components;
 pub mod graphics;
 pub mod parser;
+pub mod settings;
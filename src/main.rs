@@ -2,12 +2,31 @@ use leptos::mount_to_body;
 use leptos::view;
 use vimp::components::Canvas;
 
+/// the native cli takes the path of a vimprc as its first argument
+#[cfg(not(target_arch = "wasm32"))]
+fn vimprc_from_args() -> Option<String> {
+    let path = std::env::args().nth(1)?;
+    match std::fs::read_to_string(&path) {
+        Ok(script) => Some(script),
+        Err(err) => {
+            eprintln!("Couldn't read vimprc {path}: {err}");
+            None
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn vimprc_from_args() -> Option<String> {
+    None
+}
+
 fn main() {
     console_error_panic_hook::set_once();
 
+    let vimprc = vimprc_from_args();
     mount_to_body(|| {
         view! {
-            <Canvas/>
+            <Canvas vimprc=vimprc/>
         }
     });
     println!("Hello, world!");
@@ -1,15 +1,19 @@
 use std::fmt::{Debug, Display, Formatter};
 
-use leptos::logging;
+use leptos::{logging, use_context};
 
 use crate::{
-    components::get_cursor_pos,
-    graphics::{Circle, Form, Line, Rect, Text},
+    components::{get_cursor_pos, get_mark, snap_to_grid},
+    graphics::{plain_color, Circle, Fill, Form, Line, Rect, Text},
+    settings::{Settings, DEFAULT_DISTANCES},
 };
 
 pub mod coords;
+pub mod keys;
+pub mod operator;
+pub mod resize;
+pub use coords::{is_mark_name, Coords, Direction, RelCoordPair, CANVAS_SIZE, MARK};
 use coords::{AbsCoord, CoordFSM, RelCoord};
-pub use coords::{Coords, Direction, RelCoordPair};
 
 #[derive(Debug, Clone)]
 pub enum CommandType {
@@ -17,7 +21,7 @@ pub enum CommandType {
     Line,
     Rectangle,
     Text,
-    Circle(u32),
+    Circle(NumBuf),
 }
 
 impl Display for CommandType {
@@ -36,11 +40,15 @@ impl Display for CommandType {
     }
 }
 
+/// starts the corner radius of rects, ``~2`` or ``~2,4`` for different rx and ry
+const CORNERS: char = '~';
+
 #[derive(Debug, Clone)]
 pub struct CreateComFSM {
     coords: Option<Result<Coords, CoordFSM>>,
     ctype: CommandType,
     color: Option<String>,
+    corners: Option<String>,
     mods: Modifiers,
 }
 
@@ -50,9 +58,27 @@ pub struct Command {
     coords: Coords,
     ctype: CommandType,
     color: Option<String>,
+    /// rx and ry
+    corners: Option<(f32, f32)>,
     mods: Modifiers,
 }
 
+/// gradients and hatches (``@lin(...)``) only fill rects and circles
+fn check_color(ctype: &CommandType, color: &str) -> Result<(), String> {
+    match ctype {
+        CommandType::Rectangle | CommandType::Circle(_) => Fill::parse(color).map(|_| ()),
+        _ => plain_color(color),
+    }
+}
+
+/// None if the radius is malformed (``~,`` or ``~2,``)
+fn parse_corners(str: &str) -> Option<(f32, f32)> {
+    match str.split_once(',') {
+        Some((rx, ry)) => Some((rx.parse().ok()?, ry.parse().ok()?)),
+        None => str.parse().ok().map(|r| (r, r)),
+    }
+}
+
 impl TryInto<Form> for CreateComFSM {
     type Error = CommandType;
     fn try_into(self) -> Result<Form, Self::Error> {
@@ -76,7 +102,7 @@ impl From<CreateComFSM> for Command {
             Some(Ok(coords)) => coords,
             Some(Err(fsm)) => {
                 if let CommandType::Circle(rad) = value.ctype
-                    && rad == 0
+                    && rad == NumBuf::default()
                     && let CoordFSM::Rel(RelCoord::EnteringFirstNum(real_rad)) = fsm
                 {
                     value.ctype = CommandType::Circle(real_rad);
@@ -89,6 +115,7 @@ impl From<CreateComFSM> for Command {
             coords,
             ctype: value.ctype,
             color: value.color,
+            corners: value.corners.as_deref().and_then(parse_corners),
             mods: value.mods,
         }
     }
@@ -100,6 +127,7 @@ impl Command {
         start_coords: Option<Coords>,
         coords: Coords,
         color: Option<String>,
+        corners: Option<(f32, f32)>,
         mods: Modifiers,
     ) -> Self {
         Self {
@@ -107,6 +135,7 @@ impl Command {
             coords,
             ctype,
             color,
+            corners,
             mods,
         }
     }
@@ -119,10 +148,13 @@ impl Command {
     pub fn color(&self) -> Option<String> {
         self.color.clone()
     }
+    pub fn corners(&self) -> Option<(f32, f32)> {
+        self.corners
+    }
     pub fn mods(&self) -> &Modifiers {
         &self.mods
     }
-    pub fn start_coords(&self) -> (u32, u32) {
+    pub fn start_coords(&self) -> (f32, f32) {
         match &self.start_coords {
             None => get_cursor_pos(),
             Some(c) => c.resolve(),
@@ -199,6 +231,42 @@ impl Modifiers {
 }
 
 impl CreateComFSM {
+    /// human readable interpretation of what the command would do if it was finished now
+    pub fn describe(&self) -> String {
+        let com = Command::from(self.clone());
+        let (x, y) = com.coords().resolve();
+        let mut ret = match com.ctype() {
+            CommandType::Move => format!("move cursor to {x};{y}"),
+            CommandType::Line => format!("line to {x};{y}"),
+            CommandType::Rectangle => format!("rectangle to {x};{y}"),
+            CommandType::Text => format!("text at {x};{y}"),
+            CommandType::Circle(rad) => format!("circle at {x};{y} with radius {rad}"),
+        };
+        match (&self.corners, com.corners()) {
+            (_, Some((rx, ry))) => ret.push_str(&format!(" with corners {rx};{ry}")),
+            (Some(buf), None) if buf.is_empty() => ret.push_str(" with corners ..."),
+            (Some(buf), None) => ret.push_str(&format!(" with invalid corners \"{buf}\"")),
+            (None, None) => {}
+        }
+        if let Some(color) = com.color() {
+            match check_color(&com.ctype(), &color) {
+                Ok(()) => ret.push_str(&format!(" in {color}")),
+                Err(err) => ret.push_str(&format!(" in {color} ({err})")),
+            }
+        }
+        let mods = com.mods();
+        if mods.cursor_is_middle() {
+            ret.push_str(", cursor is middle");
+        }
+        if mods.collide() {
+            ret.push_str(", until collision");
+        }
+        if mods.move_cursor() {
+            ret.push_str(", then move cursor");
+        }
+        ret
+    }
+
     pub fn from(str: String) -> FSMResult {
         if str.is_empty() {
             return FSMResult::Err('\0');
@@ -221,18 +289,22 @@ impl CreateComFSM {
         let mut coords = None;
         let ctype = match next_char {
             'l' => CommandType::Line,
-            'm' => CommandType::Move,
+            // ``m`` sets marks, so moves that start with fast coords are on ``M``
+            'M' => CommandType::Move,
             'r' => CommandType::Rectangle,
             't' => CommandType::Text,
-            'c' => CommandType::Circle(0),
+            'c' => CommandType::Circle(NumBuf::default()),
             'a' => {
-                coords = Some(Err(CoordFSM::Abs(AbsCoord::EnteringFirstNum(0))));
+                coords = Some(Err(CoordFSM::Abs(AbsCoord::EnteringFirstNum(
+                    NumBuf::default(),
+                ))));
                 CommandType::Move
             }
-            '0'..='9' => {
-                coords = Some(Err(CoordFSM::Rel(RelCoord::EnteringFirstNum(
-                    next_char.to_digit(10).unwrap(),
-                ))));
+            '0'..='9' | '-' => {
+                coords = Some(Err(CoordFSM::Rel(RelCoord::EnteringFirstNum(push_num(
+                    NumBuf::default(),
+                    next_char,
+                )))));
                 CommandType::Move
             }
             _ => {
@@ -244,6 +316,7 @@ impl CreateComFSM {
             coords,
             ctype,
             color: None,
+            corners: None,
             mods: Modifiers::new(),
         })
     }
@@ -267,6 +340,21 @@ impl CreateComFSM {
     }
 
     pub fn advance(mut self, next_char: char) -> Result<Command, Self> {
+        // the color has to come last, so the corners are done once it starts
+        if matches!(next_char, '\n' | ';' | '@')
+            && let Some(buf) = &self.corners
+            && parse_corners(buf).is_none()
+        {
+            logging::error!("Not a valid corner radius: {buf}");
+            return Err(self);
+        }
+        if matches!(next_char, '\n' | ';')
+            && let Some(color) = &self.color
+            && let Err(err) = check_color(&self.ctype, color)
+        {
+            logging::error!("{err}");
+            return Err(self);
+        }
         if next_char == '\n' {
             return Ok(Command::from(self));
         }
@@ -277,6 +365,39 @@ impl CreateComFSM {
                 ..self
             });
         }
+        if self.color.is_none() {
+            if next_char == CORNERS {
+                let coords_done = match &self.coords {
+                    Some(Ok(_)) => true,
+                    Some(Err(fsm)) => fsm.is_finishable(),
+                    None => false,
+                };
+                if !matches!(self.ctype, CommandType::Rectangle)
+                    || !coords_done
+                    || self.corners.is_some()
+                {
+                    logging::error!("Corners only come once, after the coords of a rectangle");
+                    return Err(self);
+                }
+                return Err(Self {
+                    corners: Some(String::new()),
+                    ..self
+                });
+            }
+            if let Some(ref mut str) = self.corners {
+                return match next_char {
+                    ';' => Ok(Command::from(self)),
+                    '0'..='9' | '.' | ',' => {
+                        str.push(next_char);
+                        Err(self)
+                    }
+                    _ => {
+                        logging::error!("Not part of a corner radius: {next_char}");
+                        Err(self)
+                    }
+                };
+            }
+        }
         if let Some(ref mut str) = self.color {
             logging::log!("Got part of color: {next_char}");
             return match next_char {
@@ -305,24 +426,12 @@ impl CreateComFSM {
             }
 
             match self.coords {
-                None => match next_char {
-                    '0'..='9' => Err(Self {
-                        coords: Some(Err(CoordFSM::Rel(RelCoord::EnteringFirstNum(
-                            next_char.to_digit(10).unwrap(),
-                        )))),
-                        ..self
-                    }),
-                    'a' => Err(Self {
-                        coords: Some(Err(CoordFSM::Abs(AbsCoord::EnteringFirstNum(0)))),
-                        ..self
-                    }),
-                    _ if FastDirection::try_from(next_char).is_ok() => Err(Self {
-                        coords: Some(Err(CoordFSM::Rel(RelCoord::Direction(
-                            FastDirection::try_from(next_char).unwrap(),
-                        )))),
+                None => match CoordFSM::start(next_char) {
+                    Some(fsm) => Err(Self {
+                        coords: Some(Err(fsm)),
                         ..self
                     }),
-                    _ => {
+                    None => {
                         logging::error!("Not valid coord begin: {next_char}");
                         Err(self)
                     }
@@ -330,7 +439,7 @@ impl CreateComFSM {
                 Some(ref fsm) => match fsm {
                     Ok(coords) => match self.ctype {
                         CommandType::Circle(num) => match next_char {
-                            '0'..='9' => Err(Self {
+                            '0'..='9' | '.' => Err(Self {
                                 ctype: CommandType::Circle(push_num(num, next_char)),
                                 ..self
                             }),
@@ -339,6 +448,7 @@ impl CreateComFSM {
                                 coords: coords.clone(),
                                 ctype: self.ctype,
                                 color: None,
+                                corners: None,
                                 mods: self.mods,
                             }),
                             c => {
@@ -393,11 +503,11 @@ enum FastDirectionType {
 }
 
 impl FastDirectionType {
-    fn resolve(&self, dist: u32) -> i32 {
+    fn resolve(&self, dist: u32) -> f32 {
         match self {
-            Self::Pos => dist as i32,
-            Self::Neg => -(dist as i32),
-            Self::None => 0,
+            Self::Pos => dist as f32,
+            Self::Neg => -(dist as f32),
+            Self::None => 0.,
         }
     }
 }
@@ -463,17 +573,79 @@ impl Into<char> for FastDirection {
 trait AutoHide {
     fn to_string_autohide(&self) -> String;
 }
-impl AutoHide for u32 {
+impl AutoHide for NumBuf {
     fn to_string_autohide(&self) -> String {
-        if *self == 0 {
+        if *self == NumBuf::default() {
             return "".to_string();
         }
         self.to_string()
     }
 }
 
-fn push_num(num: u32, digit: char) -> u32 {
-    num * 10 + digit.to_digit(10).unwrap()
+/// a number that is still being typed in, remembers if (and how many) decimals were entered
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NumBuf {
+    value: f32,
+    decimals: Option<i32>,
+    negative: bool,
+}
+
+impl NumBuf {
+    pub fn value(&self) -> f32 {
+        if self.negative {
+            -self.value
+        } else {
+            self.value
+        }
+    }
+}
+
+impl From<f32> for NumBuf {
+    fn from(value: f32) -> Self {
+        Self {
+            value: value.abs(),
+            decimals: None,
+            negative: value < 0.,
+        }
+    }
+}
+
+impl Display for NumBuf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        if self.negative {
+            write!(f, "-")?;
+        }
+        match self.decimals {
+            None => write!(f, "{}", self.value),
+            Some(0) => write!(f, "{}.", self.value),
+            Some(decimals) => write!(f, "{:.*}", decimals as usize, self.value),
+        }
+    }
+}
+
+/// accepts digits, a (single) decimal point and a leading minus
+fn push_num(num: NumBuf, digit: char) -> NumBuf {
+    match (digit, num.decimals) {
+        ('-', _) if num == NumBuf::default() => NumBuf {
+            negative: true,
+            ..num
+        },
+        ('-', _) => num,
+        ('.', None) => NumBuf {
+            decimals: Some(0),
+            ..num
+        },
+        ('.', Some(_)) => num,
+        (_, None) => NumBuf {
+            value: num.value * 10. + digit.to_digit(10).unwrap() as f32,
+            ..num
+        },
+        (_, Some(decimals)) => NumBuf {
+            value: num.value + digit.to_digit(10).unwrap() as f32 / 10f32.powi(decimals + 1),
+            decimals: Some(decimals + 1),
+            ..num
+        },
+    }
 }
 
 const SHORT_5: char = 'q';
@@ -482,13 +654,16 @@ const SHORT_25: char = 'r';
 const SHORT_50: char = 't';
 const SHORT_75: char = 'z';
 
+/// the distances can be changed with ``:set distances=...``, falls back to the defaults if there
+/// are no Settings in context
 fn short_distance(value: char) -> Result<u32, ()> {
+    let dists = use_context::<Settings>().map_or(DEFAULT_DISTANCES, |s| s.distances());
     Ok(match value {
-        SHORT_5 => 5,
-        SHORT_15 => 15,
-        SHORT_25 => 25,
-        SHORT_50 => 50,
-        SHORT_75 => 75,
+        SHORT_5 => dists[0],
+        SHORT_15 => dists[1],
+        SHORT_25 => dists[2],
+        SHORT_50 => dists[3],
+        SHORT_75 => dists[4],
         _ => return Err(()),
     })
 }
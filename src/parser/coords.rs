@@ -1,18 +1,35 @@
 use crate::parser::{short_distance, FastDirection};
 use leptos::logging;
 
-use super::get_cursor_pos;
 use super::push_num;
 use super::ModifierType;
+use super::NumBuf;
+use super::{get_cursor_pos, get_mark, snap_to_grid};
 use std::fmt::{Debug, Display, Formatter};
 
 mod rel_coords;
 pub use rel_coords::{FinishedRelCoord, RelCoord, RelCoordPair};
 
+/// coords are percentages, so everything outside of 0..=100 is off the canvas
+pub const CANVAS_SIZE: f32 = 100.;
+
+pub fn clamp_to_canvas(p: (f32, f32)) -> (f32, f32) {
+    (p.0.clamp(0., CANVAS_SIZE), p.1.clamp(0., CANVAS_SIZE))
+}
+
+/// starts a mark as coords (``'a``), also jumps to one in normal mode
+pub const MARK: char = '\'';
+
+pub fn is_mark_name(c: char) -> bool {
+    c.is_ascii_lowercase()
+}
+
 #[derive(Debug, Clone)]
 pub enum Coords {
-    AbsCoord(u32, u32),
+    AbsCoord(f32, f32),
     RelCoord(FinishedRelCoord),
+    /// a position saved with ``m{a-z}``
+    Mark(char),
 }
 
 impl From<CoordFSM> for Coords {
@@ -20,6 +37,7 @@ impl From<CoordFSM> for Coords {
         match value {
             CoordFSM::Abs(abs) => abs.get_coords(),
             CoordFSM::Rel(rc) => Coords::from(rc),
+            CoordFSM::Mark => Coords::from_cursor(),
         }
     }
 }
@@ -29,25 +47,49 @@ impl Coords {
         let (x, y) = get_cursor_pos();
         Self::AbsCoord(x, y)
     }
-    pub fn resolve(&self) -> (u32, u32) {
-        match self {
+    /// needs GridSettings to be in context, the result is snapped if snap mode is on and
+    /// always stays on the canvas
+    pub fn resolve(&self) -> (f32, f32) {
+        clamp_to_canvas(snap_to_grid(match self {
             Coords::AbsCoord(x, y) => (*x, *y),
             Coords::RelCoord(fcp) => fcp.resolve_fcp(),
+            // marks were on the canvas when they got set, no need to snap them
+            Coords::Mark(name) => return Self::mark_pos(*name),
+        }))
+    }
+    /// how far a form gets moved by these coords, absolute coords are treated as an offset,
+    /// marks move by the distance from the cursor to them
+    pub fn movement(&self) -> (f32, f32) {
+        match self {
+            Coords::AbsCoord(x, y) => (*x, *y),
+            Coords::RelCoord(fcp) => fcp.offset(),
+            Coords::Mark(name) => {
+                let ((x, y), (cx, cy)) = (Self::mark_pos(*name), get_cursor_pos());
+                (x - cx, y - cy)
+            }
         }
     }
+    /// the FSM only accepts marks that are set, so this only falls back to the cursor
+    /// if something else made up the mark
+    fn mark_pos(name: char) -> (f32, f32) {
+        get_mark(name).unwrap_or_else(|| {
+            logging::error!("Mark not set: {name}");
+            get_cursor_pos()
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum AbsCoord {
-    EnteringFirstNum(u32),
-    EnteringSecondNum(u32, u32),
+    EnteringFirstNum(NumBuf),
+    EnteringSecondNum(NumBuf, NumBuf),
 }
 
 impl AbsCoord {
     fn get_coords(&self) -> Coords {
         match self {
-            Self::EnteringFirstNum(num) => Coords::AbsCoord(*num, 0),
-            Self::EnteringSecondNum(num, num2) => Coords::AbsCoord(*num, *num2),
+            Self::EnteringFirstNum(num) => Coords::AbsCoord(num.value(), 0.),
+            Self::EnteringSecondNum(num, num2) => Coords::AbsCoord(num.value(), num2.value()),
         }
     }
 }
@@ -74,10 +116,13 @@ impl AbsCoord {
     fn advance(self, next_char: char) -> Result<Coords, CoordFSM> {
         match self {
             Self::EnteringFirstNum(num) => match next_char {
-                '0'..='9' => Err(CoordFSM::Abs(Self::EnteringFirstNum(push_num(
+                '0'..='9' | '.' => Err(CoordFSM::Abs(Self::EnteringFirstNum(push_num(
                     num, next_char,
                 )))),
-                ';' => Err(CoordFSM::Abs(Self::EnteringSecondNum(num, 0))),
+                ';' => Err(CoordFSM::Abs(Self::EnteringSecondNum(
+                    num,
+                    NumBuf::default(),
+                ))),
                 _ if short_distance(next_char).is_ok()
                     || ModifierType::try_from(next_char).is_ok() =>
                 {
@@ -92,11 +137,11 @@ impl AbsCoord {
                 }
             },
             Self::EnteringSecondNum(num1, num) => match next_char {
-                '0'..='9' => Err(CoordFSM::Abs(Self::EnteringSecondNum(
+                '0'..='9' | '.' => Err(CoordFSM::Abs(Self::EnteringSecondNum(
                     num1,
                     push_num(num, next_char),
                 ))),
-                ';' => Ok(Coords::AbsCoord(num1, num)),
+                ';' => Ok(Coords::AbsCoord(num1.value(), num.value())),
                 _ => {
                     logging::error!("Not part of AbsCoord Syntax (second num): {next_char}");
                     Err(CoordFSM::Abs(self))
@@ -110,6 +155,8 @@ impl AbsCoord {
 pub enum CoordFSM {
     Abs(AbsCoord),
     Rel(RelCoord),
+    /// waiting for the name of the mark
+    Mark,
 }
 
 impl Display for CoordFSM {
@@ -120,12 +167,43 @@ impl Display for CoordFSM {
             match self {
                 Self::Abs(c) => c.to_string(),
                 Self::Rel(c) => c.to_string(),
+                Self::Mark => MARK.to_string(),
             }
         )
     }
 }
 
 impl CoordFSM {
+    /// whether these coords already mean something on their own, so ``<CR>`` wouldn't
+    /// cut off a number or a mark name
+    pub fn is_finishable(&self) -> bool {
+        matches!(
+            self,
+            Self::Abs(AbsCoord::EnteringSecondNum(..))
+                | Self::Rel(
+                    RelCoord::FirstNumAndDirection(_)
+                        | RelCoord::BothNums(..)
+                        | RelCoord::Direction(_)
+                        | RelCoord::EnteringDistance(..)
+                )
+        )
+    }
+
+    /// the state after the first char of some coords, None if no coords start with it
+    pub fn start(next_char: char) -> Option<Self> {
+        Some(match next_char {
+            '0'..='9' | '-' => Self::Rel(RelCoord::EnteringFirstNum(push_num(
+                NumBuf::default(),
+                next_char,
+            ))),
+            'a' => Self::Abs(AbsCoord::EnteringFirstNum(NumBuf::default())),
+            MARK => Self::Mark,
+            _ => Self::Rel(RelCoord::Direction(
+                FastDirection::try_from(next_char).ok()?,
+            )),
+        })
+    }
+
     pub fn advance(self, next_char: char) -> Result<Coords, Self> {
         match self {
             Self::Abs(absc) => match absc.advance(next_char) {
@@ -136,6 +214,13 @@ impl CoordFSM {
                 Ok(coords) => Ok(Coords::RelCoord(coords)),
                 Err(next_state) => Err(Self::Rel(next_state)),
             },
+            Self::Mark if is_mark_name(next_char) && get_mark(next_char).is_some() => {
+                Ok(Coords::Mark(next_char))
+            }
+            Self::Mark => {
+                logging::error!("Not a set mark: {next_char}");
+                Err(self)
+            }
         }
     }
 }
@@ -143,10 +228,14 @@ impl CoordFSM {
 impl From<Coords> for CoordFSM {
     fn from(value: Coords) -> Self {
         match value {
-            Coords::AbsCoord(x, y) => Self::Abs(AbsCoord::EnteringSecondNum(x, y)),
+            Coords::AbsCoord(x, y) => Self::Abs(AbsCoord::EnteringSecondNum(x.into(), y.into())),
             Coords::RelCoord(frc) => {
                 let (x, y) = frc.resolve_fcp();
-                Self::Abs(AbsCoord::EnteringSecondNum(x, y))
+                Self::Abs(AbsCoord::EnteringSecondNum(x.into(), y.into()))
+            }
+            mark @ Coords::Mark(_) => {
+                let (x, y) = mark.resolve();
+                Self::Abs(AbsCoord::EnteringSecondNum(x.into(), y.into()))
             }
         }
     }
@@ -157,24 +246,21 @@ impl From<RelCoord> for Coords {
         let ret = match value {
             RelCoord::FirstNumAndDirection(rcp) => FinishedRelCoord::OneCoord(rcp),
             RelCoord::EnteringFirstNum(_) => {
-                FinishedRelCoord::OneCoord(RelCoordPair(0, Direction::Up))
+                FinishedRelCoord::OneCoord(RelCoordPair(0., Direction::Up))
             }
             RelCoord::EnteringSecondNum(rcp, _) => FinishedRelCoord::OneCoord(rcp),
             RelCoord::BothNums(rcp1, rcp2) => FinishedRelCoord::TwoCoords(rcp1, rcp2),
             RelCoord::EnteringDistance(dir, dist) => {
                 logging::log!("{dir:?}: {dist}");
                 let (x, y) = get_cursor_pos();
-                return Coords::AbsCoord(
-                    (x as i32 + dir.horiz.resolve(dist)) as u32,
-                    (y as i32 + dir.vert.resolve(dist)) as u32, // this is horrible
-                );
+                let (x, y) =
+                    clamp_to_canvas((x + dir.horiz.resolve(dist), y + dir.vert.resolve(dist)));
+                return Coords::AbsCoord(x, y);
             }
             RelCoord::Direction(dir) => {
                 let (x, y) = get_cursor_pos();
-                return Coords::AbsCoord(
-                    (x as i32 + dir.horiz.resolve(5)) as u32,
-                    (y as i32 + dir.vert.resolve(5)) as u32, // this is horrible
-                );
+                let (x, y) = clamp_to_canvas((x + dir.horiz.resolve(5), y + dir.vert.resolve(5)));
+                return Coords::AbsCoord(x, y);
             }
         };
 
@@ -1,5 +1,7 @@
-use super::{DOWN, LEFT, RIGHT, UP};
-use crate::parser::{get_cursor_pos, push_num, short_distance, AutoHide, Direction, FastDirection};
+use super::{clamp_to_canvas, DOWN, LEFT, RIGHT, UP};
+use crate::parser::{
+    get_cursor_pos, push_num, short_distance, AutoHide, Direction, FastDirection, NumBuf,
+};
 use leptos::logging;
 use std::fmt::{Debug, Display, Formatter};
 
@@ -10,19 +12,26 @@ pub enum FinishedRelCoord {
 }
 
 impl FinishedRelCoord {
-    pub fn resolve_with_offset(&self, off: (u32, u32)) -> (u32, u32) {
-        let (x, y) = off;
+    /// the (signed) vector these coords describe
+    pub fn offset(&self) -> (f32, f32) {
         match self {
-            Self::OneCoord(rcp) => rcp.get_coords(x, y),
+            Self::OneCoord(rcp) => rcp.offset(),
             Self::TwoCoords(rcp, rcp2) => {
-                let (x, y) = rcp.get_coords(x, y);
-                rcp2.get_coords(x, y)
+                let (x, y) = rcp.offset();
+                let (x2, y2) = rcp2.offset();
+                (x + x2, y + y2)
             }
         }
     }
 
+    pub fn resolve_with_offset(&self, off: (f32, f32)) -> (f32, f32) {
+        let (x, y) = off;
+        let (dx, dy) = self.offset();
+        clamp_to_canvas((x + dx, y + dy))
+    }
+
     /// needs CursorSetter to be in context
-    pub fn resolve_fcp(&self) -> (u32, u32) {
+    pub fn resolve_fcp(&self) -> (f32, f32) {
         self.resolve_with_offset(get_cursor_pos())
     }
 }
@@ -31,9 +40,9 @@ impl RelCoord {
     pub fn advance(self, next_char: char) -> Result<FinishedRelCoord, Self> {
         match self {
             Self::EnteringFirstNum(num) => match next_char {
-                '0'..='9' => Err(Self::EnteringFirstNum(push_num(num, next_char))),
+                '0'..='9' | '.' | '-' => Err(Self::EnteringFirstNum(push_num(num, next_char))),
                 LEFT | DOWN | UP | RIGHT => Err(Self::FirstNumAndDirection(RelCoordPair(
-                    num,
+                    num.value(),
                     next_char.into(),
                 ))),
                 _ if short_distance(next_char).is_ok() => Err(Self::EnteringDistance(
@@ -47,10 +56,10 @@ impl RelCoord {
             },
             Self::FirstNumAndDirection(ref rcp) => match next_char {
                 '\n' => Ok(FinishedRelCoord::OneCoord(rcp.clone())),
-                ';' => Err(Self::EnteringSecondNum(rcp.clone(), 0)),
-                '0'..='9' => Err(Self::EnteringSecondNum(
+                ';' => Err(Self::EnteringSecondNum(rcp.clone(), NumBuf::default())),
+                '0'..='9' | '.' | '-' => Err(Self::EnteringSecondNum(
                     rcp.clone(),
-                    next_char.to_digit(10).unwrap(),
+                    push_num(NumBuf::default(), next_char),
                 )),
                 _ => {
                     logging::error!("Not part of RelCoord Syntax (second num): {next_char}");
@@ -58,13 +67,13 @@ impl RelCoord {
                 }
             },
             Self::EnteringSecondNum(ref rcp, num) => match next_char {
-                '0'..='9' => Err(Self::EnteringSecondNum(
+                '0'..='9' | '.' | '-' => Err(Self::EnteringSecondNum(
                     rcp.clone(),
                     push_num(num, next_char),
                 )),
                 LEFT | DOWN | UP | RIGHT => Ok(FinishedRelCoord::TwoCoords(
                     rcp.clone(),
-                    RelCoordPair(num, next_char.into()),
+                    RelCoordPair(num.value(), next_char.into()),
                 )),
                 _ => {
                     logging::error!(
@@ -101,21 +110,24 @@ impl RelCoord {
 }
 
 #[derive(Debug, Clone)]
-pub struct RelCoordPair(pub u32, pub Direction);
+pub struct RelCoordPair(pub f32, pub Direction);
 
 impl RelCoordPair {
-    pub fn get_coords(&self, x: u32, y: u32) -> (u32, u32) {
-        // FIXME: this panics if one tries to move a form even partially out-of-bounds, the
-        // solution to this should be to truncate the form to fit, however this requires knowing
-        // both points, which cannot be known by this + this is a widely used API, so changing it
-        // will be painful
+    /// the (signed) vector this pair describes, negative distances go the opposite direction
+    pub fn offset(&self) -> (f32, f32) {
         match self.1 {
-            Direction::Up => (x, y.checked_sub(self.0).unwrap_or(0)), // hotfix to prevent panics
-            Direction::Down => (x, y + self.0),
-            Direction::Left => (x.checked_sub(self.0).unwrap_or(0), y), // hotfix to prevent panics
-            Direction::Right => (x + self.0, y),
+            Direction::Up => (0., -self.0),
+            Direction::Down => (0., self.0),
+            Direction::Left => (-self.0, 0.),
+            Direction::Right => (self.0, 0.),
         }
     }
+
+    /// result gets clamped to the canvas, use offset() if you need to keep a form's shape
+    pub fn get_coords(&self, x: f32, y: f32) -> (f32, f32) {
+        let (dx, dy) = self.offset();
+        clamp_to_canvas((x + dx, y + dy))
+    }
 }
 
 impl Display for RelCoordPair {
@@ -126,9 +138,9 @@ impl Display for RelCoordPair {
 
 #[derive(Debug, Clone)]
 pub enum RelCoord {
-    EnteringFirstNum(u32),
+    EnteringFirstNum(NumBuf),
     FirstNumAndDirection(RelCoordPair),
-    EnteringSecondNum(RelCoordPair, u32),
+    EnteringSecondNum(RelCoordPair, NumBuf),
     BothNums(RelCoordPair, RelCoordPair),
     // second route
     Direction(FastDirection),
@@ -0,0 +1,77 @@
+use std::fmt::{Display, Formatter};
+
+/// a single key press, key is named like KeyboardEvent.key() names it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyInput {
+    pub key: String,
+    pub ctrl: bool,
+}
+
+impl KeyInput {
+    pub fn new(key: &str, ctrl: bool) -> Self {
+        Self {
+            key: key.to_string(),
+            ctrl,
+        }
+    }
+}
+
+fn special_key(name: &str) -> Option<&'static str> {
+    Some(match &*name.to_lowercase() {
+        "cr" | "enter" | "return" => "Enter",
+        "esc" => "Escape",
+        "bs" => "Backspace",
+        "tab" => "Tab",
+        "space" => " ",
+        "lt" => "<",
+        _ => return None,
+    })
+}
+
+/// parses vim's key notation (e.g. ``a10;10;<CR>`` or ``<C-o>``), everything that doesn't look
+/// like a special key gets typed as is
+pub fn parse_keys(str: &str) -> Vec<KeyInput> {
+    let mut ret = Vec::with_capacity(str.len());
+    let mut rest = str;
+    while let Some(c) = rest.chars().next() {
+        if c == '<'
+            && let Some(end) = rest.find('>')
+        {
+            let name = &rest[1..end];
+            let parsed = match name.split_once('-') {
+                Some((prefix, key)) if prefix.eq_ignore_ascii_case("c") && !key.is_empty() => {
+                    Some(KeyInput::new(special_key(key).unwrap_or(key), true))
+                }
+                _ => special_key(name).map(|key| KeyInput::new(key, false)),
+            };
+            if let Some(key) = parsed {
+                ret.push(key);
+                rest = &rest[end + 1..];
+                continue;
+            }
+        }
+        ret.push(KeyInput::new(&c.to_string(), false));
+        rest = &rest[c.len_utf8()..];
+    }
+    ret
+}
+
+impl Display for KeyInput {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let name = match &*self.key {
+            "Enter" => "CR",
+            "Escape" => "Esc",
+            "Backspace" => "BS",
+            "Tab" => "Tab",
+            " " => "Space",
+            "<" => "lt",
+            key if self.ctrl => key,
+            key => return write!(f, "{key}"),
+        };
+        if self.ctrl {
+            write!(f, "<C-{name}>")
+        } else {
+            write!(f, "<{name}>")
+        }
+    }
+}
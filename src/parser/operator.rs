@@ -0,0 +1,223 @@
+use std::fmt::{Display, Formatter};
+
+use leptos::logging;
+
+use super::coords::{CoordFSM, Coords};
+
+/// ``c`` already starts a circle, so change is on ``C``
+#[derive(Debug, Clone)]
+pub enum Operator {
+    Delete,
+    Yank,
+    Group,
+    /// the new color, the current one if it is left empty
+    Change(String),
+}
+
+impl Operator {
+    pub fn new(next_char: char) -> Option<Self> {
+        Some(match next_char {
+            'd' => Self::Delete,
+            'y' => Self::Yank,
+            'g' => Self::Group,
+            'C' => Self::Change(String::new()),
+            _ => return None,
+        })
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Delete => "delete",
+                Self::Yank => "yank",
+                Self::Group => "group",
+                Self::Change(_) => "change the color of",
+            }
+        )
+    }
+}
+
+/// what an operator works on
+#[derive(Debug, Clone)]
+pub enum Target {
+    /// the names of the select mode overlays, ``a,b``
+    Labels(Vec<String>),
+    /// every form inside the box from the cursor to the coords
+    Region(Coords),
+    All,
+}
+
+/// starts a region target (``[`` followed by coords)
+const REGION: char = '[';
+/// finishes a region whose coords could go on
+const REGION_END: char = ']';
+const ALL: char = '*';
+
+impl Display for Target {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::Labels(names) => write!(f, "forms {}", names.join(",")),
+            Self::Region(coords) => {
+                let (x, y) = coords.resolve();
+                write!(f, "the forms inside the region to {x};{y}")
+            }
+            Self::All => write!(f, "all forms"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum OperatorState {
+    Target,
+    Labels(String),
+    /// None until the first char of the coords
+    Region(Option<CoordFSM>),
+    /// only for change, the target is done and the color is being typed
+    Color(Target),
+}
+
+#[derive(Debug, Clone)]
+pub struct OperatorCommand {
+    pub op: Operator,
+    pub target: Target,
+}
+
+/// vim-style operator followed by its target, e.g. ``da,b;`` or ``y[20l10j]``
+#[derive(Debug, Clone)]
+pub struct OperatorFSM {
+    op: Operator,
+    state: OperatorState,
+}
+
+impl OperatorFSM {
+    pub fn new(next_char: char) -> Option<Self> {
+        Some(Self {
+            op: Operator::new(next_char)?,
+            state: OperatorState::Target,
+        })
+    }
+
+    /// replays the keys typed so far, used after a backspace
+    pub fn from_keys(keys: &str) -> Option<Self> {
+        let mut it = keys.chars();
+        let mut ret = Self::new(it.next()?)?;
+        for next_char in it {
+            // a finished command would have been run already
+            ret = ret.advance(next_char).err()?;
+        }
+        Some(ret)
+    }
+
+    /// human readable interpretation of what the operator would do if it was finished now
+    pub fn describe(&self) -> String {
+        let target = match &self.state {
+            OperatorState::Target => return format!("{} ...", self.op),
+            OperatorState::Labels(names) => Self::labels(names).to_string(),
+            OperatorState::Region(None) => return format!("{} the forms inside ...", self.op),
+            OperatorState::Region(Some(fsm)) => {
+                Target::Region(Coords::from(fsm.clone())).to_string()
+            }
+            OperatorState::Color(target) => target.to_string(),
+        };
+        match &self.op {
+            Operator::Change(color) if !color.is_empty() => {
+                format!("{} {target} to {color}", self.op)
+            }
+            op => format!("{op} {target}"),
+        }
+    }
+
+    fn labels(names: &str) -> Target {
+        Target::Labels(
+            names
+                .split(',')
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect(),
+        )
+    }
+
+    /// change still needs a color once the target is done
+    fn finish(self, target: Target) -> Result<OperatorCommand, Self> {
+        match self.op {
+            Operator::Change(_) if !matches!(self.state, OperatorState::Color(_)) => Err(Self {
+                state: OperatorState::Color(target),
+                ..self
+            }),
+            op => Ok(OperatorCommand { op, target }),
+        }
+    }
+
+    pub fn advance(self, next_char: char) -> Result<OperatorCommand, Self> {
+        match self.state.clone() {
+            OperatorState::Target => match next_char {
+                ALL => self.finish(Target::All),
+                REGION => Err(Self {
+                    state: OperatorState::Region(None),
+                    ..self
+                }),
+                'a'..='z' => Err(Self {
+                    state: OperatorState::Labels(next_char.to_string()),
+                    ..self
+                }),
+                _ => {
+                    logging::error!("Not a valid operator target: {next_char}");
+                    Err(self)
+                }
+            },
+            OperatorState::Labels(mut names) => match next_char {
+                '\n' | ';' => self.finish(Self::labels(&names)),
+                'a'..='z' | ',' => {
+                    names.push(next_char);
+                    Err(Self {
+                        state: OperatorState::Labels(names),
+                        ..self
+                    })
+                }
+                _ => {
+                    logging::error!("Not part of a label: {next_char}");
+                    Err(self)
+                }
+            },
+            OperatorState::Region(fsm) => match (fsm, next_char) {
+                (None, '\n' | REGION_END) => self.finish(Target::Region(Coords::from_cursor())),
+                (Some(fsm), '\n' | REGION_END) => self.finish(Target::Region(Coords::from(fsm))),
+                (None, _) => match CoordFSM::start(next_char) {
+                    Some(fsm) => Err(Self {
+                        state: OperatorState::Region(Some(fsm)),
+                        ..self
+                    }),
+                    None => {
+                        logging::error!("Not valid coord begin: {next_char}");
+                        Err(self)
+                    }
+                },
+                (Some(fsm), _) => match fsm.advance(next_char) {
+                    Ok(coords) => self.finish(Target::Region(coords)),
+                    Err(fsm) => Err(Self {
+                        state: OperatorState::Region(Some(fsm)),
+                        ..self
+                    }),
+                },
+            },
+            OperatorState::Color(target) => match (self.op, next_char) {
+                (op, '\n' | ';') => Ok(OperatorCommand { op, target }),
+                (Operator::Change(mut color), _) => {
+                    color.push(next_char);
+                    Err(Self {
+                        op: Operator::Change(color),
+                        state: OperatorState::Color(target),
+                    })
+                }
+                (op, _) => Err(Self {
+                    op,
+                    state: OperatorState::Color(target),
+                }),
+            },
+        }
+    }
+}
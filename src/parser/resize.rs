@@ -0,0 +1,114 @@
+use leptos::logging;
+
+use super::coords::{CoordFSM, Coords, RelCoord};
+use super::FastDirection;
+
+/// the edge or corner that gets moved, -1, 0 or 1 on each axis like the fast directions
+/// (``d`` is the right edge, ``q`` the top left corner, ...)
+pub type Handle = (f32, f32);
+
+#[derive(Debug, Clone)]
+pub struct Resize {
+    pub handle: Handle,
+    /// how far the handle moves
+    pub by: (f32, f32),
+}
+
+/// unfinished fast coords turn into a position, but a resize needs how far they go
+fn offset(fsm: CoordFSM) -> (f32, f32) {
+    match fsm {
+        CoordFSM::Rel(RelCoord::EnteringDistance(dir, dist)) => {
+            (dir.horiz.resolve(dist), dir.vert.resolve(dist))
+        }
+        CoordFSM::Rel(RelCoord::Direction(dir)) => (dir.horiz.resolve(5), dir.vert.resolve(5)),
+        fsm => Coords::from(fsm).movement(),
+    }
+}
+
+/// ``s`` with forms selected, followed by a handle and the coords it gets moved by,
+/// e.g. ``sd10l<CR>`` grows rects to the right
+#[derive(Debug, Clone, Default)]
+pub struct ResizeFSM {
+    handle: Option<Handle>,
+    coords: Option<CoordFSM>,
+}
+
+impl ResizeFSM {
+    /// replays the keys typed after ``s``, used after a backspace
+    pub fn from_keys(keys: &str) -> Option<Self> {
+        let mut ret = Self::default();
+        for next_char in keys.chars() {
+            // a finished resize would have been done already
+            ret = ret.advance(next_char).err()?;
+        }
+        Some(ret)
+    }
+
+    pub fn describe(&self) -> String {
+        let Some((x, y)) = self.handle else {
+            return "resize (pick an edge or corner)".to_string();
+        };
+        let edge = match (x as i8, y as i8) {
+            (-1, -1) => "top left corner",
+            (0, -1) => "top edge",
+            (1, -1) => "top right corner",
+            (-1, 0) => "left edge",
+            (1, 0) => "right edge",
+            (-1, 1) => "bottom left corner",
+            (0, 1) => "bottom edge",
+            _ => "bottom right corner",
+        };
+        match &self.coords {
+            None => format!("move the {edge} by ..."),
+            Some(fsm) => {
+                let (dx, dy) = offset(fsm.clone());
+                format!("move the {edge} by {dx};{dy}")
+            }
+        }
+    }
+
+    pub fn advance(self, next_char: char) -> Result<Resize, Self> {
+        match (self.handle, self.coords) {
+            (None, coords) => match FastDirection::try_from(next_char) {
+                Ok(dir) => Err(Self {
+                    handle: Some((dir.horiz.resolve(1), dir.vert.resolve(1))),
+                    coords,
+                }),
+                Err(err) => {
+                    logging::error!("{err}");
+                    Err(Self {
+                        handle: None,
+                        coords,
+                    })
+                }
+            },
+            (Some(handle), None) => match CoordFSM::start(next_char) {
+                Some(fsm) => Err(Self {
+                    handle: Some(handle),
+                    coords: Some(fsm),
+                }),
+                None => {
+                    logging::error!("Not valid coord begin: {next_char}");
+                    Err(Self {
+                        handle: Some(handle),
+                        coords: None,
+                    })
+                }
+            },
+            (Some(handle), Some(fsm)) if next_char == '\n' => Ok(Resize {
+                handle,
+                by: offset(fsm),
+            }),
+            (Some(handle), Some(fsm)) => match fsm.advance(next_char) {
+                Ok(coords) => Ok(Resize {
+                    handle,
+                    by: coords.movement(),
+                }),
+                Err(fsm) => Err(Self {
+                    handle: Some(handle),
+                    coords: Some(fsm),
+                }),
+            },
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use leptos::{logging, use_context, window, RwSignal, SignalSet};
+
+use crate::components::GridSettings;
+use crate::graphics::{plain_color, TextAnchor, DEFAULT_COLOR};
+
+const STORAGE_KEY: &str = "vimp-settings";
+
+pub const DEFAULT_STROKE_WIDTH: f32 = 2.;
+pub const DEFAULT_FONT_SIZE: u32 = 1;
+pub const DEFAULT_FONT_FAMILY: &str = "sans-serif";
+/// distances of the fast coord system keys, in the same order as SHORT_5..SHORT_75
+pub const DEFAULT_DISTANCES: [u32; 5] = [5, 15, 25, 50, 75];
+
+/// (name, abbreviation, is boolean)
+const OPTIONS: [(&str, &str, bool); 12] = [
+    ("color", "co", false),
+    ("strokewidth", "sw", false),
+    ("fontsize", "fs", false),
+    ("fontfamily", "ff", false),
+    ("bold", "bold", true),
+    ("italic", "italic", true),
+    ("textanchor", "ta", false),
+    ("distances", "dist", false),
+    ("gridspacing", "gs", false),
+    ("grid", "grid", true),
+    ("snap", "snap", true),
+    ("clipboard", "cb", false),
+];
+/// formats ``"+y`` can put into the system clipboard
+const CLIPBOARD_FORMATS: [&str; 2] = ["svg", "vimp"];
+
+/// everything that can be changed with ``:set``, gets persisted in local storage
+#[derive(Clone, Copy)]
+pub struct Settings {
+    color: RwSignal<String>,
+    stroke_width: RwSignal<f32>,
+    font_size: RwSignal<u32>,
+    font_family: RwSignal<String>,
+    bold: RwSignal<bool>,
+    italic: RwSignal<bool>,
+    anchor: RwSignal<TextAnchor>,
+    distances: RwSignal<[u32; 5]>,
+    clipboard: RwSignal<String>,
+    grid: GridSettings,
+}
+
+/// needs Settings to be in context
+pub fn settings() -> Settings {
+    use_context::<Settings>().expect("Settings not provided")
+}
+
+impl Settings {
+    pub fn new(grid: GridSettings) -> Self {
+        Self {
+            color: RwSignal::new(DEFAULT_COLOR.to_string()),
+            stroke_width: RwSignal::new(DEFAULT_STROKE_WIDTH),
+            font_size: RwSignal::new(DEFAULT_FONT_SIZE),
+            font_family: RwSignal::new(DEFAULT_FONT_FAMILY.to_string()),
+            bold: RwSignal::new(false),
+            italic: RwSignal::new(false),
+            anchor: RwSignal::new(TextAnchor::Left),
+            distances: RwSignal::new(DEFAULT_DISTANCES),
+            clipboard: RwSignal::new(CLIPBOARD_FORMATS[0].to_string()),
+            grid,
+        }
+    }
+
+    pub fn color(&self) -> String {
+        (self.color)()
+    }
+    pub fn stroke_width(&self) -> f32 {
+        (self.stroke_width)()
+    }
+    pub fn font_size(&self) -> u32 {
+        (self.font_size)()
+    }
+    pub fn font_family(&self) -> String {
+        (self.font_family)()
+    }
+    pub fn bold(&self) -> bool {
+        (self.bold)()
+    }
+    pub fn italic(&self) -> bool {
+        (self.italic)()
+    }
+    pub fn anchor(&self) -> TextAnchor {
+        (self.anchor)()
+    }
+    pub fn distances(&self) -> [u32; 5] {
+        (self.distances)()
+    }
+    pub fn clipboard(&self) -> String {
+        (self.clipboard)()
+    }
+
+    fn option(name: &str) -> Result<(&'static str, bool), String> {
+        OPTIONS
+            .iter()
+            .find(|(full, short, _)| name == *full || name == *short)
+            .map(|(full, _, boolean)| (*full, *boolean))
+            .ok_or_else(|| format!("Unknown option: {name}"))
+    }
+
+    pub fn get(&self, name: &str) -> Result<String, String> {
+        Ok(match Self::option(name)?.0 {
+            "color" => self.color(),
+            "strokewidth" => self.stroke_width().to_string(),
+            "fontsize" => self.font_size().to_string(),
+            "fontfamily" => self.font_family(),
+            "bold" => Self::format_bool("bold", self.bold()),
+            "italic" => Self::format_bool("italic", self.italic()),
+            "textanchor" => self.anchor().to_string(),
+            "distances" => self.distances().map(|dist| dist.to_string()).join(","),
+            "gridspacing" => self.grid.spacing().to_string(),
+            "grid" => Self::format_bool("grid", self.grid.visible()),
+            "snap" => Self::format_bool("snap", self.grid.snap()),
+            "clipboard" => self.clipboard(),
+            _ => unreachable!(),
+        })
+    }
+
+    pub fn set(&self, name: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("Invalid value for {name}: {value}");
+        match Self::option(name)?.0 {
+            // new lines and texts get it too
+            "color" => {
+                plain_color(value)?;
+                self.color.set(value.to_string())
+            }
+            "strokewidth" => self.stroke_width.set(value.parse().map_err(|_| invalid())?),
+            "fontsize" => self.font_size.set(value.parse().map_err(|_| invalid())?),
+            "fontfamily" => self.font_family.set(value.to_string()),
+            "textanchor" => self.anchor.set(value.parse()?),
+            "distances" => {
+                let dists = value
+                    .split(',')
+                    .map(|dist| dist.parse::<u32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| invalid())?;
+                let dists: [u32; 5] = dists
+                    .try_into()
+                    .map_err(|_| format!("distances needs exactly 5 values: {value}"))?;
+                self.distances.set(dists);
+            }
+            "gridspacing" => self.grid.set_spacing(value.parse().map_err(|_| invalid())?),
+            "clipboard" if CLIPBOARD_FORMATS.contains(&value) => {
+                self.clipboard.set(value.to_string())
+            }
+            "clipboard" => return Err(invalid()),
+            "grid" | "snap" | "bold" | "italic" => {
+                return Err(format!("{name} is a boolean option"))
+            }
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn set_bool(&self, name: &str, value: impl FnOnce(bool) -> bool) -> Result<(), String> {
+        match Self::option(name)?.0 {
+            "grid" => self.grid.set_visible(value(self.grid.visible())),
+            "snap" => self.grid.set_snap(value(self.grid.snap())),
+            "bold" => self.bold.set(value(self.bold())),
+            "italic" => self.italic.set(value(self.italic())),
+            _ => return Err(format!("{name} is not a boolean option")),
+        }
+        Ok(())
+    }
+
+    fn format_bool(name: &str, value: bool) -> String {
+        if value {
+            name.to_string()
+        } else {
+            format!("no{name}")
+        }
+    }
+
+    /// handles one argument of ``:set`` (``opt=val``, ``opt?``, ``opt``, ``noopt``, ``opt!``)
+    pub fn apply(&self, arg: &str) -> Result<Option<String>, String> {
+        if let Some((name, value)) = arg.split_once('=') {
+            self.set(name, value)?;
+        } else if let Some(name) = arg.strip_suffix('?') {
+            return Ok(Some(format!("{name}={}", self.get(name)?)));
+        } else if let Some(name) = arg.strip_suffix('!') {
+            self.set_bool(name, |val| !val)?;
+        } else if Self::option(arg).is_ok_and(|(_, boolean)| boolean) {
+            self.set_bool(arg, |_| true)?;
+        } else if let Some(name) = arg.strip_prefix("no")
+            && Self::option(name).is_ok_and(|(_, boolean)| boolean)
+        {
+            self.set_bool(name, |_| false)?;
+        } else {
+            // like vim, naming a non-boolean option shows its value
+            return Ok(Some(format!("{arg}={}", self.get(arg)?)));
+        }
+        Ok(None)
+    }
+
+    /// all options in a form ``apply`` understands
+    pub fn to_args(&self) -> Vec<String> {
+        OPTIONS
+            .iter()
+            .map(|(name, _, boolean)| {
+                let value = self.get(name).unwrap();
+                if *boolean {
+                    value
+                } else {
+                    format!("{name}={value}")
+                }
+            })
+            .collect()
+    }
+
+    pub fn save(&self) {
+        let args = self.to_args().join("\n");
+        match window().local_storage() {
+            Ok(Some(storage)) => {
+                if let Err(err) = storage.set_item(STORAGE_KEY, &args) {
+                    logging::warn!("Couldn't save settings: {err:?}");
+                }
+            }
+            _ => logging::warn!("No local storage, settings won't be saved"),
+        }
+    }
+
+    pub fn load(&self) {
+        let stored = match window().local_storage() {
+            Ok(Some(storage)) => storage.get_item(STORAGE_KEY).ok().flatten(),
+            _ => None,
+        };
+        for arg in stored.iter().flat_map(|str| str.lines()) {
+            if let Err(err) = self.apply(arg) {
+                logging::warn!("Ignoring stored setting '{arg}': {err}");
+            }
+        }
+    }
+}